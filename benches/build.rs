@@ -0,0 +1,94 @@
+//! Measures `build` across power-of-two leaf counts, which take the fast path with no odd node
+//! at any level, alongside the next leaf count up, which doesn't.
+//!
+//! When the fast path was added it made no difference outside of noise (about 5% either way
+//! at 2^10, 2^14 and 2^17 leaves): checking for the odd node is negligible next to hashing.
+//!
+//! The million-leaf group was added alongside sizing the leaf hash buffer for every level up
+//! front, which saves one reallocation of the leaf level. That too was within noise, at about
+//! a second per build either way.
+//!
+//! The node hashing group compares combining two children by feeding each to Keccak in turn,
+//! as `build` now does, with hashing a freshly allocated `left || right`. Skipping the
+//! allocation measured 514 ns against 532 ns per node, which is barely outside noise: the
+//! Keccak permutation dominates either way.
+//!
+//! The builder group compares adding a million leaves to `MerkleBuilder::with_capacity` with
+//! letting `MerkleBuilder::new` grow as it goes. Both took about a second, within noise of each
+//! other: growing copies around 32 MB of leaf hashes in all, which is little next to a million
+//! Keccak hashes.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use murky::{build, hash_leaf, hash_nodes, Keccak256, MerkleBuilder, MerkleHasher, MerkleTree};
+
+fn build_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for k in [10, 14, 17] {
+        for n in [1usize << k, (1 << k) + 1] {
+            let leaves: Vec<String> = (0..n).map(|i| i.to_string()).collect();
+            group.bench_with_input(BenchmarkId::from_parameter(n), &leaves, |b, leaves| {
+                b.iter(|| black_box(build(leaves)))
+            });
+        }
+    }
+    group.finish();
+}
+
+// A million leaves, where reallocating even once copies tens of megabytes of hashes
+fn build_million(c: &mut Criterion) {
+    let leaves: Vec<String> = (0..1 << 20).map(|i: usize| i.to_string()).collect();
+    let mut group = c.benchmark_group("build_1m");
+    group.sample_size(10);
+    group.bench_function("build", |b| b.iter(|| black_box(build(&leaves))));
+    group.bench_function("new", |b| {
+        b.iter_batched(
+            || leaves.clone(),
+            |leaves| black_box(MerkleTree::new(leaves)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn node_hashing(c: &mut Criterion) {
+    let (left, right) = (hash_leaf(b"left"), hash_leaf(b"right"));
+    let mut group = c.benchmark_group("hash_nodes");
+    group.bench_function("incremental", |b| {
+        b.iter(|| hash_nodes(black_box(&left), black_box(&right)))
+    });
+    group.bench_function("concat", |b| {
+        b.iter(|| Keccak256::digest(&[black_box(left), black_box(right)].concat()))
+    });
+    group.finish();
+}
+
+// Adding a million leaves one at a time, as an ingestion pipeline would
+fn builder_million(c: &mut Criterion) {
+    let leaves: Vec<String> = (0..1 << 20).map(|i: usize| i.to_string()).collect();
+    let mut group = c.benchmark_group("builder_1m");
+    group.sample_size(10);
+    group.bench_function("new", |b| {
+        b.iter(|| {
+            let mut builder = MerkleBuilder::new();
+            leaves.iter().for_each(|leaf| builder.add_leaf(leaf));
+            black_box(builder)
+        })
+    });
+    group.bench_function("with_capacity", |b| {
+        b.iter(|| {
+            let mut builder = MerkleBuilder::with_capacity(leaves.len());
+            leaves.iter().for_each(|leaf| builder.add_leaf(leaf));
+            black_box(builder)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    build_sizes,
+    build_million,
+    node_hashing,
+    builder_million
+);
+criterion_main!(benches);
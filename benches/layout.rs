@@ -0,0 +1,60 @@
+//! Compares building a tree into the flat single-buffer layout `MerkleTree` uses against the
+//! nested `Vec<Vec<_>>` layout it used to store.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use murky::{Hashable, Keccak256, MerkleHasher, MerkleTree};
+
+const LEAVES: usize = 1 << 20;
+
+// The previous layout: one allocation per level, collected leaves-first then reversed
+fn build_nested(leaf_hashes: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    // Pass each leaf through `Hashable` as the tree does, so only the layout differs
+    let leaf_hashes: Vec<[u8; 32]> = leaf_hashes
+        .iter()
+        .map(|leaf| leaf.hash_bytes().try_into().unwrap())
+        .collect();
+    let mut hashes = vec![leaf_hashes];
+    while let Some(level) = hashes.last().filter(|level| level.len() > 1) {
+        let parents = level
+            .chunks(2)
+            .map(|pair| {
+                let right = pair.get(1).unwrap_or(&pair[0]);
+                Keccak256::digest(&[pair[0], *right].concat())
+            })
+            .collect();
+        hashes.push(parents);
+    }
+    hashes.reverse();
+    hashes
+}
+
+fn layout(c: &mut Criterion) {
+    let leaf_hashes: Vec<[u8; 32]> = (0..LEAVES)
+        .map(|i| Keccak256::digest(i.to_string().as_bytes()))
+        .collect();
+    assert_eq!(
+        build_nested(leaf_hashes.clone())[0][0],
+        MerkleTree::<[u8; 32]>::from_hashes(leaf_hashes.clone()).root_hash()
+    );
+
+    let mut group = c.benchmark_group("build 1M leaves");
+    group.sample_size(10);
+    group.bench_function("nested", |b| {
+        b.iter_batched(
+            || leaf_hashes.clone(),
+            |leaves| black_box(build_nested(leaves)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("flat", |b| {
+        b.iter_batched(
+            || leaf_hashes.clone(),
+            |leaves| black_box(MerkleTree::<[u8; 32]>::from_hashes(leaves)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, layout);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Measures verifying a single proof, whose cost is one hash per level. Each pair is fed to the
+//! hasher in turn rather than copied into a fresh `Vec`, which leaves a single allocation per
+//! call, for the leaf's bytes.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use murky::{verify_proof, HashAlgo, MerkleTree};
+
+fn verify(c: &mut Criterion) {
+    let leaves: Vec<String> = (0..1 << 16).map(|i: u32| i.to_string()).collect();
+    let tree = MerkleTree::new(leaves);
+    let proof = tree.get_proof(12_345).to_pairs();
+    let root = tree.root_hash();
+    assert!(verify_proof("12345", &proof, root, HashAlgo::Keccak256));
+
+    c.bench_function("verify_proof 2^16 leaves", |b| {
+        b.iter(|| {
+            verify_proof(
+                black_box("12345"),
+                black_box(&proof),
+                root,
+                HashAlgo::Keccak256,
+            )
+        })
+    });
+}
+
+criterion_group!(benches, verify);
+criterion_main!(benches);
@@ -0,0 +1,170 @@
+use alloc::vec::Vec;
+
+use crate::{GenericMerkleTree, Hashable, MerkleHasher, Proof};
+
+/// A proof that a value is not a leaf of a tree whose leaves are sorted, from
+/// [`GenericMerkleTree::prove_absence`].
+///
+/// It proves the inclusion of the value's neighbours, the greatest leaf below it and the least
+/// leaf above it, at adjacent positions. Since nothing fits between two adjacent leaves of a
+/// sorted tree, the value can't be in it. A value below every leaf has only an upper
+/// neighbour, at the first position, and one above every leaf only a lower neighbour, at the
+/// last position.
+///
+/// Leaves are compared by their [`Hashable::hash_bytes`], as
+/// [`GenericMerkleTree::new_sorted_leaves`] orders them with [`crate::LeafOrder::Bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsenceProof<const N: usize = 32> {
+    /// The bytes of the greatest leaf below the value and its inclusion proof.
+    pub lower: Option<(Vec<u8>, Proof<N>)>,
+    /// The bytes of the least leaf above the value and its inclusion proof.
+    pub upper: Option<(Vec<u8>, Proof<N>)>,
+}
+
+impl<T: Hashable, H: MerkleHasher<N>, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Proves that `value` isn't a leaf of the tree, see [`AbsenceProof`].
+    ///
+    /// Returns `None` if `value` is a leaf, or if the leaves aren't in ascending order of
+    /// their [`Hashable::hash_bytes`]. Checking the order takes a pass over every leaf. Like
+    /// [`GenericMerkleTree::proof`], it also returns `None` for a pruned tree, which has no
+    /// siblings to prove the neighbours with.
+    ///
+    /// The leaves of a tree from [`crate::GenericMerkleBuilder`] are the hashes of the values
+    /// it was given, so `value` is hashed the same way before it's compared with them.
+    pub fn prove_absence<L: Hashable + ?Sized>(&self, value: &L) -> Option<AbsenceProof<N>> {
+        let leaves: Vec<Vec<u8>> = self.leaves.iter().map(Hashable::hash_bytes).collect();
+        if !leaves.windows(2).all(|pair| pair[0] <= pair[1]) {
+            return None;
+        }
+
+        let value = match self.scheme.value_lookups {
+            true => self.scheme.lookup_hash::<H, L, N>(value)?.to_vec(),
+            false => value.hash_bytes(),
+        };
+        let position = leaves.partition_point(|leaf| *leaf < value);
+        if leaves.get(position) == Some(&value) {
+            return None;
+        }
+        let neighbour = |index: usize| {
+            let proof = Proof {
+                leaf_index: index,
+                leaf_count: leaves.len(),
+                siblings: self.proof(index)?,
+            };
+            Some((leaves[index].clone(), proof))
+        };
+        let lower = match position.checked_sub(1) {
+            Some(index) => Some(neighbour(index)?),
+            None => None,
+        };
+        let upper = match position < leaves.len() {
+            true => Some(neighbour(position)?),
+            false => None,
+        };
+        Some(AbsenceProof { lower, upper })
+    }
+}
+
+impl AbsenceProof {
+    /// Checks that `value` is absent from the Keccak-256 tree of `leaf_count` leaves with root
+    /// `root`, as built by [`crate::MerkleTree::new_sorted_leaves`].
+    ///
+    /// The root doesn't commit to the number of leaves, so like [`crate::verify_consistency`]
+    /// this takes it from the caller, who must know it from a trusted source: a proof that
+    /// claims a smaller tree can pass off a leaf in the middle as the last one. The proof only
+    /// shows absence if the root is also known to commit to sorted leaves: an unsorted tree
+    /// can have the value elsewhere, between two adjacent leaves that happen to bracket it.
+    pub fn verify<L: Hashable + ?Sized>(
+        &self,
+        value: &L,
+        root: [u8; 32],
+        leaf_count: usize,
+    ) -> bool {
+        let value = value.hash_bytes();
+        let mut neighbours = self.lower.iter().chain(&self.upper);
+        // Checked first, so every index below is known to be within `leaf_count`
+        if !neighbours.all(|(leaf, proof)| {
+            proof.leaf_count == leaf_count && proof.verify(leaf.as_slice(), root)
+        }) {
+            return false;
+        }
+        match (&self.lower, &self.upper) {
+            (Some((lower, below)), Some((upper, above))) => {
+                *lower < value && value < *upper && below.leaf_index + 1 == above.leaf_index
+            }
+            (Some((lower, below)), None) => *lower < value && below.leaf_index + 1 == leaf_count,
+            (None, Some((upper, above))) => value < *upper && above.leaf_index == 0,
+            (None, None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LeafOrder, MerkleBuilder, MerkleTree};
+
+    #[test]
+    fn absence_proofs() {
+        let leaves: Vec<String> = ["b", "d", "f", "h", "j"].map(String::from).to_vec();
+        let tree = MerkleTree::new_sorted_leaves(leaves.clone(), LeafOrder::Bytes);
+        let root = tree.root_hash();
+
+        for value in ["a", "c", "e", "g", "i", "k", "dd"] {
+            let proof = tree.prove_absence(value).unwrap();
+            assert!(proof.verify(value, root, 5));
+        }
+        assert_eq!(tree.prove_absence("a").unwrap().lower, None);
+        assert_eq!(tree.prove_absence("k").unwrap().upper, None);
+        assert!(tree.prove_absence("f").is_none());
+
+        // A proof only covers values between its two neighbours
+        let proof = tree.prove_absence("e").unwrap();
+        assert!(!proof.verify("c", root, 5));
+        assert!(!proof.verify("d", root, 5));
+        assert!(!proof.verify("e", MerkleTree::new(vec!["x"]).root_hash(), 1));
+        assert!(!proof.verify("e", root, 6));
+
+        // Neighbours that aren't adjacent don't prove anything
+        let mut gapped = proof.clone();
+        gapped.upper = tree.prove_absence("g").unwrap().upper;
+        assert!(!gapped.verify("e", root, 5));
+
+        // A neighbour's path that still folds to the root under a smaller leaf count can't
+        // make a middle leaf pass for the last one
+        let tree = MerkleTree::new_sorted_leaves(leaves[..4].to_vec(), LeafOrder::Bytes);
+        let root = tree.root_hash();
+        let forged = Proof {
+            leaf_index: 2,
+            leaf_count: 3,
+            siblings: tree.proof(2).unwrap(),
+        };
+        assert!(forged.verify("f", root));
+        let forged = AbsenceProof {
+            lower: Some((b"f".to_vec(), forged)),
+            upper: None,
+        };
+        assert!(!forged.verify("h", root, 4));
+
+        let unsorted = MerkleTree::new(vec!["b", "a", "c"]);
+        assert!(unsorted.prove_absence("bb").is_none());
+
+        let mut pruned = tree.clone();
+        pruned.prune();
+        assert!(pruned.prove_absence("e").is_none());
+        pruned.rehydrate();
+        assert_eq!(pruned.prove_absence("e"), tree.prove_absence("e"));
+
+        // A built tree's leaves are hashes, so a value is only found once it's hashed too
+        let mut builder = MerkleBuilder::new();
+        builder.extend(["f", "h", "0123456789abcdef0123456789abcdef"]);
+        let mut built = builder.build().unwrap();
+        built.leaves_mut().unwrap().sort();
+        built.rebuild_in_place().unwrap();
+        assert!(built
+            .prove_absence("0123456789abcdef0123456789abcdef")
+            .is_none());
+        assert!(built.prove_absence("f").is_none());
+        assert!(built.prove_absence("g").is_some());
+    }
+}
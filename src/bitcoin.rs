@@ -0,0 +1,80 @@
+use alloc::vec::Vec;
+
+use crate::{build_levels, DoubleSha256, MerkleError, Scheme};
+
+/// Builds every level of a Bitcoin transaction Merkle tree over `txids`, root first.
+///
+/// Unlike [`crate::build`], leaves are txids that are already hashes, so they form the leaf
+/// level unchanged, and nodes are hashed with [`DoubleSha256`] rather than Keccak-256. The last
+/// node of an odd level is duplicated, as in the default scheme. Txids and the root are in
+/// Bitcoin's internal byte order, the reverse of how block explorers display them.
+///
+/// The duplication rule means `[a, b, c]` and `[a, b, c, c]` share a root; use
+/// [`build_bitcoin_checked`] to reject the second.
+pub fn build_bitcoin(txids: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let scheme = Scheme {
+        prehashed: true,
+        ..Scheme::default()
+    };
+    build_levels::<DoubleSha256, [u8; 32], 32>(&txids, scheme).to_nested()
+}
+
+/// Like [`build_bitcoin`], but returns [`MerkleError::DuplicateSiblings`] for the first pair
+/// of identical nodes, mirroring the `mutated` check Bitcoin Core uses against CVE-2012-2459.
+///
+/// A node duplicated to fill an odd level isn't a pair of distinct nodes and is allowed.
+pub fn build_bitcoin_checked(txids: Vec<[u8; 32]>) -> Result<Vec<Vec<[u8; 32]>>, MerkleError> {
+    let levels = build_bitcoin(txids);
+    for (level, nodes) in levels.iter().enumerate().skip(1) {
+        if let Some(pair) = nodes.chunks_exact(2).position(|pair| pair[0] == pair[1]) {
+            return Err(MerkleError::DuplicateSiblings {
+                level,
+                index: pair * 2,
+            });
+        }
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Txids as shown by block explorers, converted to internal byte order
+    fn txid(display: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(display, &mut bytes).unwrap();
+        bytes.reverse();
+        bytes
+    }
+
+    #[test]
+    fn block_100000() {
+        let txids = vec![
+            txid("8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87"),
+            txid("fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4"),
+            txid("6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4"),
+            txid("e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d"),
+        ];
+        let levels = build_bitcoin_checked(txids).unwrap();
+        assert_eq!(
+            levels[0][0],
+            txid("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766")
+        );
+    }
+
+    #[test]
+    fn rejects_duplicated_txids() {
+        let [a, b, c] = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        assert_eq!(
+            build_bitcoin(vec![a, b, c])[0],
+            build_bitcoin(vec![a, b, c, c])[0]
+        );
+
+        assert!(build_bitcoin_checked(vec![a, b, c]).is_ok());
+        assert_eq!(
+            build_bitcoin_checked(vec![a, b, c, c]),
+            Err(MerkleError::DuplicateSiblings { level: 2, index: 2 })
+        );
+    }
+}
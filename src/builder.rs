@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{GenericMerkleTree, Hashable, Keccak256, MerkleError, MerkleHasher, Scheme};
+
+/// Builds a tree from leaves supplied one at a time, hashing each as it arrives.
+///
+/// Only the leaf hashes are kept, not the leaves themselves, so the finished tree is a
+/// pre-hashed one (see [`GenericMerkleTree::from_hashes`]) with the same hashes and root as
+/// building directly from the leaves. Unlike a tree built from hashes, it's looked up by the
+/// original leaf values: [`GenericMerkleTree::contains`] and the other lookups hash the value
+/// they're given just as [`GenericMerkleBuilder::add_leaf`] did.
+///
+/// Most code should use the [`MerkleBuilder`] alias, which fixes the hasher to [`Keccak256`].
+#[derive(Debug)]
+pub struct GenericMerkleBuilder<H, const N: usize = 32> {
+    leaf_hashes: Vec<[u8; N]>,
+    hasher: PhantomData<fn() -> H>,
+}
+
+/// A Keccak-256 tree builder, the crate's default.
+pub type MerkleBuilder = GenericMerkleBuilder<Keccak256>;
+
+impl<H: MerkleHasher<N>, const N: usize> GenericMerkleBuilder<H, N> {
+    pub fn new() -> Self {
+        GenericMerkleBuilder {
+            leaf_hashes: Vec::new(),
+            hasher: PhantomData,
+        }
+    }
+
+    /// A builder with room for `capacity` leaves, so that adding up to that many never
+    /// reallocates, for callers that know their leaf count up front.
+    pub fn with_capacity(capacity: usize) -> Self {
+        GenericMerkleBuilder {
+            leaf_hashes: Vec::with_capacity(capacity),
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn add_leaf<L: Hashable>(&mut self, leaf: L) {
+        self.leaf_hashes
+            .push(Scheme::default().hash_leaf::<H, L, N>(&leaf));
+    }
+
+    /// The number of leaves added so far.
+    pub fn len(&self) -> usize {
+        self.leaf_hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_hashes.is_empty()
+    }
+
+    /// Finishes the tree, returning [`MerkleError::EmptyTree`] if no leaves were added.
+    pub fn build(self) -> Result<GenericMerkleTree<[u8; N], H, N>, MerkleError> {
+        let scheme = Scheme {
+            prehashed: true,
+            value_lookups: true,
+            ..Scheme::default()
+        };
+        GenericMerkleTree::with_scheme(self.leaf_hashes, scheme)
+    }
+}
+
+impl<H: MerkleHasher<N>, L: Hashable, const N: usize> Extend<L> for GenericMerkleBuilder<H, N> {
+    fn extend<I: IntoIterator<Item = L>>(&mut self, leaves: I) {
+        for leaf in leaves {
+            self.add_leaf(leaf);
+        }
+    }
+}
+
+impl<H: MerkleHasher<N>, const N: usize> Default for GenericMerkleBuilder<H, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    #[test]
+    fn matches_new() {
+        let leaves: Vec<String> = (0..11).map(|i| i.to_string()).collect();
+        let mut builder = MerkleBuilder::new();
+        for leaf in &leaves[..4] {
+            builder.add_leaf(leaf);
+        }
+        builder.extend(&leaves[4..]);
+        assert_eq!(builder.len(), 11);
+
+        let built = builder.build().unwrap();
+        let tree = MerkleTree::new(leaves);
+        assert_eq!(built.root_hash(), tree.root_hash());
+        assert_eq!(built.hashes, tree.hashes);
+    }
+
+    #[test]
+    fn lookups_by_value() {
+        // A leaf as long as a hash is still hashed on lookup, as `add_leaf` hashed it
+        let leaves = ["0123456789abcdef0123456789abcdef", "b", "c"];
+        let mut builder = MerkleBuilder::new();
+        builder.extend(leaves);
+        let built = builder.build().unwrap();
+        let tree = MerkleTree::new(leaves.to_vec());
+        assert_eq!(built.root_hash(), tree.root_hash());
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            assert!(built.contains(leaf));
+            assert_eq!(built.index_of(leaf), Some(index));
+            assert_eq!(built.proof_for_value(leaf), tree.proof_for_value(leaf));
+        }
+        assert!(!built.contains("d"));
+        assert!(!built.contains(&built.leaves()[1]));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let leaves: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let mut builder = MerkleBuilder::with_capacity(leaves.len());
+        let buffer = builder.leaf_hashes.as_ptr();
+        builder.extend(&leaves);
+        assert_eq!(builder.leaf_hashes.as_ptr(), buffer);
+
+        let built = builder.build().unwrap();
+        assert_eq!(built.hashes, MerkleTree::new(leaves).hashes);
+    }
+
+    #[test]
+    fn empty_builder() {
+        assert_eq!(MerkleBuilder::new().build(), Err(MerkleError::EmptyTree));
+    }
+}
@@ -0,0 +1,278 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::levels::Levels;
+use crate::{
+    GenericMerkleTree, LeafEncoding, MerkleError, MerkleHasher, NodeEncoding, OddStrategy, Scheme,
+};
+
+// Bumped whenever the layout below changes
+const VERSION: u8 = 2;
+
+// Bits of the scheme's flags byte
+const SORTED_PAIRS: u8 = 1 << 0;
+const PREHASHED: u8 = 1 << 1;
+const PAD_ZERO: u8 = 1 << 2;
+const HEX_LEAVES: u8 = 1 << 3;
+const TAGGED: u8 = 1 << 4;
+const SALTED: u8 = 1 << 5;
+const VALUE_LOOKUPS: u8 = 1 << 6;
+
+impl<H: MerkleHasher<N>, const N: usize> GenericMerkleTree<String, H, N> {
+    /// Serializes the tree into a compact binary form for [`GenericMerkleTree::from_bytes`],
+    /// without needing the `serde` feature.
+    ///
+    /// All integers are big-endian. The layout is a version byte, currently `2`, then how the
+    /// tree was built (37 bytes), the leaf count as a `u64` and each leaf as a `u64` length
+    /// followed by its UTF-8 bytes, and finally the level count as a `u64` and each level,
+    /// root first, as a `u64` width followed by its `N`-byte hashes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![VERSION];
+        encode_scheme(&self.scheme, &mut bytes);
+
+        bytes.extend_from_slice(&(self.leaves.len() as u64).to_be_bytes());
+        for leaf in &self.leaves {
+            bytes.extend_from_slice(&(leaf.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(leaf.as_bytes());
+        }
+
+        let levels = self.full_levels();
+        bytes.extend_from_slice(&(levels.len() as u64).to_be_bytes());
+        for level in levels.iter() {
+            bytes.extend_from_slice(&(level.len() as u64).to_be_bytes());
+            level.iter().for_each(|hash| bytes.extend_from_slice(hash));
+        }
+        bytes
+    }
+
+    /// Deserializes a tree written by [`GenericMerkleTree::to_bytes`].
+    ///
+    /// Like deserializing with `serde`, this rebuilds the tree from its leaves and checks the
+    /// stored hashes against it. Returns [`MerkleError::MalformedBytes`] if the data is
+    /// truncated, has trailing bytes or isn't a tree this version wrote, and
+    /// [`MerkleError::InconsistentTree`] if its hashes don't match its leaves.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, MerkleError> {
+        let mut reader = Reader(data);
+        if reader.take(1)? != [VERSION] {
+            return Err(MerkleError::MalformedBytes);
+        }
+        let scheme = decode_scheme(&mut reader)?;
+
+        // Counts aren't trusted for preallocation, as they could claim more than the data holds
+        let mut leaves = Vec::new();
+        for _ in 0..reader.u64()? {
+            let len = usize::try_from(reader.u64()?).map_err(|_| MerkleError::MalformedBytes)?;
+            let leaf =
+                core::str::from_utf8(reader.take(len)?).map_err(|_| MerkleError::MalformedBytes)?;
+            leaves.push(String::from(leaf));
+        }
+
+        let mut hashes = Vec::new();
+        for _ in 0..reader.u64()? {
+            let mut level = Vec::new();
+            for _ in 0..reader.u64()? {
+                level.push(reader.array()?);
+            }
+            hashes.push(level);
+        }
+        if !reader.0.is_empty() || leaves.is_empty() {
+            return Err(MerkleError::MalformedBytes);
+        }
+
+        let tree = GenericMerkleTree::from_parts(leaves, Levels::from_nested(hashes), scheme);
+        if !tree.verify_self() {
+            return Err(MerkleError::InconsistentTree);
+        }
+        Ok(tree)
+    }
+}
+
+fn encode_scheme(scheme: &Scheme, bytes: &mut Vec<u8>) {
+    let flags = [
+        (scheme.sorted_pairs, SORTED_PAIRS),
+        (scheme.prehashed, PREHASHED),
+        (scheme.value_lookups, VALUE_LOOKUPS),
+        (scheme.odd == OddStrategy::PadZero, PAD_ZERO),
+        (scheme.encoding == LeafEncoding::Hex, HEX_LEAVES),
+        (scheme.tags.is_some(), TAGGED),
+        (scheme.salt.is_some(), SALTED),
+    ];
+    bytes.push(
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, bit)| bit)
+            .sum(),
+    );
+
+    let (kind, separator) = match scheme.node_encoding {
+        NodeEncoding::Raw => (0, 0),
+        NodeEncoding::Separator(separator) => (1, separator),
+        NodeEncoding::LengthPrefixed => (2, 0),
+    };
+    let (leaf_tag, node_tag) = scheme.tags.unwrap_or_default();
+    bytes.extend_from_slice(&[kind, separator, leaf_tag, node_tag]);
+    bytes.extend_from_slice(&scheme.salt.unwrap_or_default());
+}
+
+fn decode_scheme(reader: &mut Reader) -> Result<Scheme, MerkleError> {
+    let [flags, kind, separator, leaf_tag, node_tag] = reader.array()?;
+    let salt: [u8; 32] = reader.array()?;
+    if flags >> 7 != 0 {
+        return Err(MerkleError::MalformedBytes);
+    }
+    // Bytes for a setting the scheme doesn't use must be zero, so each tree has exactly one
+    // encoding
+    let unused = [
+        (kind != 1 && separator != 0),
+        (flags & PREHASHED == 0 && flags & VALUE_LOOKUPS != 0),
+        (flags & TAGGED == 0 && (leaf_tag, node_tag) != (0, 0)),
+        (flags & SALTED == 0 && salt != [0; 32]),
+    ];
+    if unused.contains(&true) {
+        return Err(MerkleError::MalformedBytes);
+    }
+
+    let node_encoding = match kind {
+        0 => NodeEncoding::Raw,
+        1 => NodeEncoding::Separator(separator),
+        2 => NodeEncoding::LengthPrefixed,
+        _ => return Err(MerkleError::MalformedBytes),
+    };
+    Ok(Scheme {
+        sorted_pairs: flags & SORTED_PAIRS != 0,
+        odd: if flags & PAD_ZERO != 0 {
+            OddStrategy::PadZero
+        } else {
+            OddStrategy::DuplicateLast
+        },
+        tags: (flags & TAGGED != 0).then_some((leaf_tag, node_tag)),
+        prehashed: flags & PREHASHED != 0,
+        value_lookups: flags & VALUE_LOOKUPS != 0,
+        encoding: if flags & HEX_LEAVES != 0 {
+            LeafEncoding::Hex
+        } else {
+            LeafEncoding::Utf8
+        },
+        node_encoding,
+        salt: (flags & SALTED != 0).then_some(salt),
+    })
+}
+
+// The unread rest of the input
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MerkleError> {
+        if len > self.0.len() {
+            return Err(MerkleError::MalformedBytes);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const LEN: usize>(&mut self) -> Result<[u8; LEN], MerkleError> {
+        Ok(self.take(LEN)?.try_into().unwrap())
+    }
+
+    fn u64(&mut self) -> Result<u64, MerkleError> {
+        self.array().map(u64::from_be_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    fn leaves(n: usize) -> Vec<String> {
+        (0..n).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let trees = [
+            MerkleTree::new(leaves(1)),
+            MerkleTree::new(leaves(7)),
+            MerkleTree::new_tagged(leaves(5), 0, 1),
+            MerkleTree::new_salted(leaves(3), [7; 32]),
+            MerkleTree::new_with_node_encoding(leaves(4), NodeEncoding::Separator(b'|')),
+        ];
+        for tree in trees {
+            let bytes = tree.to_bytes();
+            assert_eq!(MerkleTree::from_bytes(&bytes), Ok(tree));
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let tree = MerkleTree::new(vec!["ab".to_string()]);
+        let bytes = tree.to_bytes();
+        assert_eq!(bytes.len(), 1 + 37 + 8 + 8 + 2 + 8 + 8 + 32);
+        assert_eq!(&bytes[38..56], b"\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\x02ab");
+        assert_eq!(&bytes[72..], &tree.root_hash());
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let bytes = MerkleTree::new(leaves(5)).to_bytes();
+        for len in [0, 1, 38, 50, bytes.len() - 1] {
+            assert_eq!(
+                MerkleTree::from_bytes(&bytes[..len]),
+                Err(MerkleError::MalformedBytes)
+            );
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            MerkleTree::from_bytes(&trailing),
+            Err(MerkleError::MalformedBytes)
+        );
+
+        // Changing the last leaf from "4" to "5" leaves the stored hashes stale
+        let mut tampered = bytes.clone();
+        let leaf = tampered.iter().position(|byte| *byte == b'4').unwrap();
+        tampered[leaf] = b'5';
+        assert_eq!(
+            MerkleTree::from_bytes(&tampered),
+            Err(MerkleError::InconsistentTree)
+        );
+
+        let mut huge_count = bytes.clone();
+        huge_count[38..46].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(
+            MerkleTree::from_bytes(&huge_count),
+            Err(MerkleError::MalformedBytes)
+        );
+
+        let mut huge_leaf = bytes.clone();
+        huge_leaf[46..54].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(
+            MerkleTree::from_bytes(&huge_leaf),
+            Err(MerkleError::MalformedBytes)
+        );
+
+        // The separator, tag and salt bytes of a scheme that doesn't use them (offsets 3, 4, 5
+        // and 6 onward) must be zero
+        for offset in [3, 4, 5, 6, 37] {
+            let mut unused = bytes.clone();
+            unused[offset] = 1;
+            assert_eq!(
+                MerkleTree::from_bytes(&unused),
+                Err(MerkleError::MalformedBytes),
+                "offset {}",
+                offset
+            );
+        }
+        // As is the flag for how a tree built from hashes is looked up, on one that isn't
+        let mut unused = bytes.clone();
+        unused[1] = VALUE_LOOKUPS;
+        assert_eq!(
+            MerkleTree::from_bytes(&unused),
+            Err(MerkleError::MalformedBytes)
+        );
+    }
+}
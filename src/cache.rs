@@ -0,0 +1,78 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{build_from_hashes, hash_leaf};
+
+/// Leaf hashes remembered across builds by [`build_with_cache`], keyed by the leaf itself.
+///
+/// The cache holds a copy of every distinct leaf it has seen plus its 32-byte hash, and never
+/// evicts anything, so long-running callers should [`LeafCache::clear`] it once the overlap
+/// between their trees moves on. The hashes are those of [`crate::build`]: they aren't valid
+/// for trees built with another hasher or scheme, which mustn't share a cache.
+#[derive(Debug, Clone, Default)]
+pub struct LeafCache {
+    hashes: BTreeMap<String, [u8; 32]>,
+}
+
+impl LeafCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of distinct leaves cached.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+    }
+
+    fn hash(&mut self, leaf: &str) -> [u8; 32] {
+        if let Some(hash) = self.hashes.get(leaf) {
+            return *hash;
+        }
+        let hash = hash_leaf(leaf.as_bytes());
+        self.hashes.insert(String::from(leaf), hash);
+        hash
+    }
+}
+
+/// Like [`crate::build`], but looks each leaf's hash up in `cache`, only hashing and caching
+/// the leaves it hasn't seen before.
+///
+/// This pays off when many trees share most of their leaves. Every level above the leaves is
+/// still hashed in full, so the saving is at most the leaf hashing, and a lookup costs a few
+/// string comparisons, which for short leaves can be slower than hashing them.
+pub fn build_with_cache(leaves: &[String], cache: &mut LeafCache) -> Vec<Vec<[u8; 32]>> {
+    build_from_hashes(leaves.iter().map(|leaf| cache.hash(leaf)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build;
+
+    #[test]
+    fn matches_build() {
+        let leaves: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let mut cache = LeafCache::new();
+
+        assert_eq!(
+            build_with_cache(&leaves[..6], &mut cache),
+            build(&leaves[..6])
+        );
+        assert_eq!(cache.len(), 6);
+        assert_eq!(build_with_cache(&leaves, &mut cache), build(&leaves));
+        assert_eq!(cache.len(), 10);
+
+        assert!(build_with_cache(&[], &mut cache).is_empty());
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
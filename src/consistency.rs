@@ -0,0 +1,223 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::frontier::peaks_root;
+use crate::levels::Levels;
+use crate::{GenericMerkleTree, Hashable, Keccak256, MerkleHasher, Scheme};
+
+impl<T: Hashable, H: MerkleHasher<N>, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Proves that the tree over the first `new_size` leaves is an append-only extension of
+    /// the tree over the first `old_size`, for [`verify_consistency`].
+    ///
+    /// This follows the shape of RFC 6962's consistency proofs, adapted to this crate's
+    /// duplication of odd nodes. The proof is the roots of the complete subtrees that make up
+    /// the old tree, smallest first, then the right-hand siblings on the path from the
+    /// smallest of them to the new root. The single subtree of a power-of-two `old_size` is
+    /// the old root itself, so it's left out, and equal sizes need an empty proof.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < old_size <= new_size <= self.leaf_count()`, or if the leaves were
+    /// edited through [`GenericMerkleTree::leaves_mut`] and not yet rebuilt.
+    pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Vec<[u8; N]> {
+        self.assert_fresh();
+        assert!(
+            0 < old_size && old_size <= new_size && new_size <= self.leaves.len(),
+            "invalid consistency proof sizes {} and {} for {} leaves",
+            old_size,
+            new_size,
+            self.leaves.len()
+        );
+        if old_size == new_size {
+            return vec![];
+        }
+
+        let scheme = self.scheme;
+        let prefix;
+        // A pruned tree still has its leaf hashes to rebuild the levels from
+        let levels = if new_size == self.leaves.len() && !self.is_pruned() {
+            &self.hashes
+        } else {
+            let leaf_hashes = self.hashes[self.hashes.len() - 1][..new_size].to_vec();
+            prefix = Levels::build(leaf_hashes, |level, index| {
+                scheme.parent::<H, N>(level, index)
+            });
+            &prefix
+        };
+        // Heights count up from the leaves, the reverse of `levels`' indexing
+        let node = |height: usize, index: usize| levels[levels.len() - 1 - height][index];
+
+        let mut proof = vec![];
+        let low = old_size.trailing_zeros() as usize;
+        if !old_size.is_power_of_two() {
+            for height in (low..usize::BITS as usize).filter(|h| old_size >> h & 1 == 1) {
+                proof.push(node(height, (old_size >> height) - 1));
+            }
+        }
+
+        let mut index = (old_size >> low) - 1;
+        for height in low..levels.len() - 1 {
+            let width = levels[levels.len() - 1 - height].len();
+            // Left siblings are the old tree's subtrees, which are already in the proof
+            if index.is_multiple_of(2) && index + 1 < width {
+                proof.push(node(height, index + 1));
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+/// Checks a [`GenericMerkleTree::consistency_proof`] that the Keccak-256 tree with root
+/// `new_root` and `new_size` leaves extends the one with root `old_root` and `old_size`
+/// leaves.
+///
+/// Returns `false` rather than panicking for any malformed proof or sizes. As with the rest of
+/// the crate, a root doesn't commit to its tree's exact leaf count, so the sizes should come
+/// from a trusted source such as a signed tree head.
+pub fn verify_consistency(
+    old_root: [u8; 32],
+    new_root: [u8; 32],
+    proof: &[[u8; 32]],
+    old_size: usize,
+    new_size: usize,
+) -> bool {
+    fold_consistency::<Keccak256, 32>(Scheme::default(), old_root, proof, old_size, new_size)
+        == Some((old_root, new_root))
+}
+
+// Recomputes the old and new roots from a consistency proof
+fn fold_consistency<H: MerkleHasher<N>, const N: usize>(
+    scheme: Scheme,
+    old_root: [u8; N],
+    proof: &[[u8; N]],
+    old_size: usize,
+    new_size: usize,
+) -> Option<([u8; N], [u8; N])> {
+    if old_size == 0 || old_size > new_size {
+        return None;
+    }
+    if old_size == new_size {
+        return proof.is_empty().then_some((old_root, old_root));
+    }
+
+    let mut proof = proof.iter();
+    let low = old_size.trailing_zeros() as usize;
+    let mut peaks = vec![[0u8; N]; usize::BITS as usize - old_size.leading_zeros() as usize];
+    if old_size.is_power_of_two() {
+        peaks[low] = old_root;
+    } else {
+        for height in (low..peaks.len()).filter(|h| old_size >> h & 1 == 1) {
+            peaks[height] = *proof.next()?;
+        }
+    }
+    let computed_old = peaks_root::<H, N>(scheme, &peaks, old_size);
+
+    let mut node = peaks[low];
+    let mut index = (old_size >> low) - 1;
+    let mut width = (new_size - 1) >> low;
+    let mut height = low;
+    while width > 0 {
+        node = if index % 2 == 1 {
+            scheme.hash_nodes::<H, N>(&peaks[height], &node)
+        } else if index < width {
+            scheme.hash_nodes::<H, N>(&node, proof.next()?)
+        } else {
+            scheme.hash_nodes::<H, N>(&node, &scheme.odd_sibling(&node))
+        };
+        index /= 2;
+        width /= 2;
+        height += 1;
+    }
+
+    if proof.next().is_some() {
+        return None;
+    }
+    Some((computed_old, node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    fn leaves(n: usize) -> Vec<String> {
+        (0..n).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn four_to_seven() {
+        let old = MerkleTree::new(leaves(4));
+        let new = MerkleTree::new(leaves(7));
+        let proof = new.consistency_proof(4, 7);
+
+        assert!(verify_consistency(
+            old.root_hash(),
+            new.root_hash(),
+            &proof,
+            4,
+            7
+        ));
+        assert!(!verify_consistency(
+            new.root_hash(),
+            old.root_hash(),
+            &proof,
+            4,
+            7
+        ));
+        assert!(!verify_consistency(
+            old.root_hash(),
+            new.root_hash(),
+            &proof,
+            3,
+            7
+        ));
+    }
+
+    #[test]
+    fn every_pair_of_sizes() {
+        let tree = MerkleTree::new(leaves(20));
+        let roots: Vec<[u8; 32]> = (0..=20)
+            .map(|n| match n {
+                0 => [0u8; 32],
+                n => MerkleTree::new(leaves(n)).root_hash(),
+            })
+            .collect();
+
+        for new_size in 1..=20 {
+            for old_size in 1..=new_size {
+                let proof = tree.consistency_proof(old_size, new_size);
+                let (old, new) = (roots[old_size], roots[new_size]);
+                assert!(
+                    verify_consistency(old, new, &proof, old_size, new_size),
+                    "{} to {}",
+                    old_size,
+                    new_size
+                );
+
+                if let Some((_, shorter)) = proof.split_last() {
+                    assert!(!verify_consistency(old, new, shorter, old_size, new_size));
+                    let mut tampered = proof.clone();
+                    tampered[0][0] ^= 1;
+                    assert!(!verify_consistency(old, new, &tampered, old_size, new_size));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_rewritten_history() {
+        let mut rewritten = leaves(7);
+        rewritten[1] = "x".to_string();
+        let old = MerkleTree::new(leaves(4));
+        let new = MerkleTree::new(rewritten);
+        let proof = new.consistency_proof(4, 7);
+        assert!(!verify_consistency(
+            old.root_hash(),
+            new.root_hash(),
+            &proof,
+            4,
+            7
+        ));
+    }
+}
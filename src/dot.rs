@@ -0,0 +1,85 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::{GenericMerkleTree, Hashable};
+
+// Hex characters of each hash shown in a node label
+const PREFIX_LEN: usize = 8;
+
+impl<T: Hashable, H, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Renders the tree as a GraphViz DOT digraph, e.g. for `dot -Tsvg`.
+    ///
+    /// Each node is labeled with the first few hex characters of its hash, and each leaf also
+    /// with its [`Hashable::display_leaf`]. Edges point from parents to their children; the odd
+    /// node at the end of a level has a single edge, as its sibling is not a separate node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree is pruned, see [`GenericMerkleTree::prune`].
+    pub fn to_dot(&self) -> String {
+        self.assert_complete();
+        let mut dot = String::from("digraph merkle {\n");
+        let leaf_level = self.hashes.len() - 1;
+
+        for (level, hashes) in self.hashes.iter().enumerate() {
+            for (index, hash) in hashes.iter().enumerate() {
+                let prefix = &hex::encode(hash)[..PREFIX_LEN.min(2 * N)];
+                let label = if level == leaf_level {
+                    format!(
+                        "{}\\n{}",
+                        prefix,
+                        escape(&self.leaves[index].display_leaf())
+                    )
+                } else {
+                    String::from(prefix)
+                };
+                dot.push_str(&format!("  n{}_{} [label=\"{}\"];\n", level, index, label));
+            }
+        }
+
+        for (level, children) in self.hashes.iter().enumerate().skip(1) {
+            for index in 0..children.len() {
+                dot.push_str(&format!(
+                    "  n{}_{} -> n{}_{};\n",
+                    level - 1,
+                    index / 2,
+                    level,
+                    index
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Leaves are arbitrary strings, so quotes and backslashes must not end the label early
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MerkleTree;
+
+    #[test]
+    fn nodes_and_edges() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        let dot = tree.to_dot();
+
+        assert!(dot.starts_with("digraph merkle {\n"));
+        assert!(dot.contains(&format!("n0_0 [label=\"{}\"]", &tree.root_hex()[..8])));
+        assert!(dot.contains("\\nc\"];"));
+        // Three leaves, two internal nodes and the root, with an edge into every non-root node
+        assert_eq!(dot.matches("[label=").count(), 6);
+        assert_eq!(dot.matches(" -> ").count(), 5);
+        assert!(dot.contains("n1_1 -> n2_2;"));
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let dot = MerkleTree::new(vec!["say \"hi\""]).to_dot();
+        assert!(dot.contains("say \\\"hi\\\""));
+    }
+}
@@ -0,0 +1,103 @@
+use core::fmt;
+
+/// Errors returned by the fallible Merkle tree operations.
+///
+/// New variants may be added as the API grows, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MerkleError {
+    /// The tree would have no leaves.
+    EmptyTree,
+    /// A leaf index was past the end of the tree.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// The leaf at `index` was expected to be hex but couldn't be decoded.
+    InvalidHex { index: usize },
+    /// The leaf at `index` decoded to `len` bytes rather than a hash of `expected` bytes.
+    InvalidHashLength {
+        index: usize,
+        len: usize,
+        expected: usize,
+    },
+    /// Serialized tree data was truncated or otherwise malformed.
+    MalformedBytes,
+    /// JSON tree data wasn't an object of the shape `to_json` writes, or held a hash that
+    /// wasn't hex of the right length.
+    MalformedJson,
+    /// A tree's stored hashes didn't match the ones rebuilt from its leaves.
+    InconsistentTree,
+    /// The tree was frozen by [`crate::GenericMerkleTree::freeze`] and can't be changed.
+    Frozen,
+    /// Two distinct nodes paired at `level` (counted root-first) and `index` were identical,
+    /// which lets a different leaf list produce the same Bitcoin root (CVE-2012-2459).
+    DuplicateSiblings { level: usize, index: usize },
+    /// Two trees that had to be the same size had `left` and `right` leaves respectively.
+    LeafCountMismatch { left: usize, right: usize },
+    /// A balanced tree was asked for over `len` leaves, which isn't a power of two.
+    NotPowerOfTwo { len: usize },
+    /// A tree would have had `len` leaves, more than the `max` it may have.
+    TooManyLeaves { len: usize, max: usize },
+    /// The tree was pruned by [`crate::GenericMerkleTree::prune`] and no longer stores the
+    /// levels needed.
+    Pruned,
+    /// The leaves were edited through [`crate::GenericMerkleTree::leaves_mut`] and the tree
+    /// hasn't been rebuilt since.
+    Stale,
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MerkleError::EmptyTree => write!(f, "cannot build a Merkle tree with no leaves"),
+            MerkleError::IndexOutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "leaf index {} is out of bounds for {} leaves",
+                    index, len
+                )
+            }
+            MerkleError::InvalidHex { index } => write!(f, "leaf {} is not valid hex", index),
+            MerkleError::InvalidHashLength {
+                index,
+                len,
+                expected,
+            } => write!(
+                f,
+                "leaf {} is {} bytes rather than a {}-byte hash",
+                index, len, expected
+            ),
+            MerkleError::MalformedBytes => write!(f, "malformed serialized Merkle tree"),
+            MerkleError::MalformedJson => write!(f, "malformed JSON Merkle tree"),
+            MerkleError::InconsistentTree => {
+                write!(f, "stored hashes do not match the tree's leaves")
+            }
+            MerkleError::Frozen => write!(f, "cannot change a frozen Merkle tree"),
+            MerkleError::DuplicateSiblings { level, index } => write!(
+                f,
+                "nodes {} and {} of level {} are identical",
+                index,
+                index + 1,
+                level
+            ),
+            MerkleError::LeafCountMismatch { left, right } => write!(
+                f,
+                "trees have different leaf counts, {} and {}",
+                left, right
+            ),
+            MerkleError::NotPowerOfTwo { len } => write!(
+                f,
+                "{} leaves can't make a balanced tree, as it isn't a power of two",
+                len
+            ),
+            MerkleError::TooManyLeaves { len, max } => {
+                write!(f, "a tree can have at most {} leaves, not {}", max, len)
+            }
+            MerkleError::Pruned => write!(f, "the Merkle tree is pruned; rehydrate it first"),
+            MerkleError::Stale => {
+                write!(f, "the Merkle tree's leaves were edited; rebuild it first")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleError {}
@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{Hashable, Keccak256, MerkleHasher, Scheme};
+
+/// An append-only tree that keeps only its right edge: one node per level, `O(log n)` in
+/// total, rather than every hash.
+///
+/// Leaves can be appended and the current root read, but not looked up, updated or proven.
+/// The root is the same as [`crate::MerkleTree::new`] over the same leaves, including the
+/// duplication of odd nodes.
+///
+/// Most code should use the [`Frontier`] alias, which fixes the hasher to [`Keccak256`].
+#[derive(Debug, Clone)]
+pub struct GenericFrontier<H, const N: usize = 32> {
+    // `nodes[level]` is the root of the complete `2^level`-leaf subtree ending the leaves, for
+    // each level whose bit is set in `len`; the others are stale
+    nodes: Vec<[u8; N]>,
+    len: usize,
+    hasher: PhantomData<fn() -> H>,
+}
+
+/// A Keccak-256 frontier, the crate's default.
+pub type Frontier = GenericFrontier<Keccak256>;
+
+impl<H: MerkleHasher<N>, const N: usize> GenericFrontier<H, N> {
+    pub fn new() -> Self {
+        GenericFrontier {
+            nodes: Vec::new(),
+            len: 0,
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn append<L: Hashable>(&mut self, leaf: L) {
+        let scheme = Scheme::default();
+        let mut node = scheme.hash_leaf::<H, L, N>(&leaf);
+
+        // Merge with each complete subtree of the same size, like carrying in binary addition
+        let mut level = 0;
+        while self.len >> level & 1 == 1 {
+            node = scheme.hash_nodes::<H, N>(&self.nodes[level], &node);
+            level += 1;
+        }
+        if level == self.nodes.len() {
+            self.nodes.push(node);
+        } else {
+            self.nodes[level] = node;
+        }
+        self.len += 1;
+    }
+
+    /// The root over every leaf appended so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no leaves have been appended.
+    pub fn root(&self) -> [u8; N] {
+        assert!(self.len > 0, "cannot build a Merkle tree with no leaves");
+        peaks_root::<H, N>(Scheme::default(), &self.nodes, self.len)
+    }
+
+    /// The number of leaves appended so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// The root of a `len`-leaf tree given its peaks: `peaks[level]` is the root of the complete
+/// `2^level`-leaf subtree ending the leaves, for each level whose bit is set in `len`. Entries
+/// for the other levels are ignored. `len` must be non-zero.
+pub(crate) fn peaks_root<H: MerkleHasher<N>, const N: usize>(
+    scheme: Scheme,
+    peaks: &[[u8; N]],
+    len: usize,
+) -> [u8; N] {
+    // `partial` is the last node of `level` when it covers fewer than `2^level` leaves
+    let mut partial: Option<[u8; N]> = None;
+    let mut level = 0;
+    while (len - 1) >> level > 0 {
+        let peak = &peaks[level];
+        partial = match (partial, len >> level & 1 == 1) {
+            // A complete last node on a level of odd width is paired as the odd node
+            (None, true) => Some(scheme.hash_nodes::<H, N>(peak, &scheme.odd_sibling(peak))),
+            (None, false) => None,
+            (Some(node), true) => Some(scheme.hash_nodes::<H, N>(peak, &node)),
+            (Some(node), false) => {
+                Some(scheme.hash_nodes::<H, N>(&node, &scheme.odd_sibling(&node)))
+            }
+        };
+        level += 1;
+    }
+    partial.unwrap_or_else(|| peaks[level])
+}
+
+impl<H: MerkleHasher<N>, const N: usize> Default for GenericFrontier<H, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    #[test]
+    fn matches_tree_root() {
+        let leaves: Vec<String> = (0..70).map(|i| i.to_string()).collect();
+        let mut frontier = Frontier::new();
+        for (i, leaf) in leaves.iter().enumerate() {
+            frontier.append(leaf);
+            assert_eq!(frontier.len(), i + 1);
+            assert_eq!(
+                frontier.root(),
+                MerkleTree::new(leaves[..=i].to_vec()).root_hash(),
+                "{} leaves",
+                i + 1
+            );
+        }
+        assert!(frontier.nodes.len() <= 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_root() {
+        Frontier::new().root();
+    }
+}
@@ -0,0 +1,67 @@
+//! Roots pinned to literal values, so that any change to leaf hashing, the order children are
+//! concatenated in or the odd-node rule fails a test rather than silently changing every
+//! commitment.
+//!
+//! The expected roots were computed outside this crate, by a short script folding each level
+//! with OpenSSL's `KECCAK-256` digest.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{build, MerkleTree, OddStrategy};
+
+fn leaves(values: &[&str]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn default_roots() {
+    let fixtures: [(&[&str], &str); 6] = [
+        (
+            &["a"],
+            "3ac225168df54212a25c1c01fd35bebfea408fdac2e31ddd6f80a4bbf9a5f1cb",
+        ),
+        (
+            &["a", "b"],
+            "805b21d846b189efaeb0377d6bb0d201b3872a363e607c25088f025b0c6ae1f8",
+        ),
+        (
+            &["a", "b", "c", "d"],
+            "68203f90e9d07dc5859259d7536e87a6ba9d345f2552b5b9de2999ddce9ce1bf",
+        ),
+        // The `odd_leaf_tree` case: "e" is paired with itself twice on the way up
+        (
+            &["a", "b", "c", "d", "e"],
+            "e467c863b0e6b42bf57865b1d965372858094a175d68652a3d158954f688e22f",
+        ),
+        (
+            &["e", "d", "c", "b", "a"],
+            "6dbd5bb40e07d89d60006a33911fd29c6893e8b8d4249906c95f015ed952a63b",
+        ),
+        (
+            &["0", "1", "2", "3", "4", "5", "6"],
+            "225c8bc1032ec9bfc3feb9d74101184716da2badf2acbd2c06f038798165f904",
+        ),
+    ];
+    for (values, root) in fixtures {
+        let tree = MerkleTree::new(leaves(values));
+        assert_eq!(tree.root_hex(), root, "leaves {:?}", values);
+        assert_eq!(hex::encode(build(values)[0][0]), root);
+    }
+}
+
+#[test]
+fn scheme_roots() {
+    assert_eq!(
+        MerkleTree::new_sorted(leaves(&["e", "d", "c", "b", "a"])).root_hex(),
+        "05ad7afe50ab2b3c46ab3b1f042a5ce67c748dd52b81faf469bacf98912edbbe"
+    );
+    assert_eq!(
+        MerkleTree::new_with(leaves(&["a", "b", "c"]), OddStrategy::PadZero).root_hex(),
+        "bb7435dc6d054fe2f2d80bc6d511c4ee773787506cb84b59f9f3f3ed2d9f7a90"
+    );
+    assert_eq!(
+        MerkleTree::new_tagged(leaves(&["a", "b", "c"]), 0x00, 0x01).root_hex(),
+        "b22c0efd0d3e042bd140fbbad6f313b2cedbcc1b3b30abab7b96cddefb4b806d"
+    );
+}
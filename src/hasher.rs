@@ -0,0 +1,161 @@
+use tiny_keccak::Hasher;
+
+/// Identifies the hash function a tree was built with, since a bare root doesn't say.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgo {
+    Keccak256,
+    /// 64-byte Keccak-512, see [`Keccak512`].
+    Keccak512,
+    Sha256,
+    Blake3,
+    /// Any hasher other than the crate's own full-width ones, including [`TruncatedKeccak256`].
+    Custom,
+}
+
+/// A hash function used to build a Merkle tree, for both leaves and internal nodes.
+///
+/// `N` is the digest size in bytes, which is also the size of every node in the tree.
+pub trait MerkleHasher<const N: usize = 32> {
+    /// The algorithm reported by [`crate::GenericMerkleTree::algo`] for trees using this hasher.
+    const ALGO: HashAlgo = HashAlgo::Custom;
+
+    /// Hashes `data` into an `N`-byte digest.
+    fn digest(data: &[u8]) -> [u8; N];
+
+    /// Hashes the concatenation of `parts`, exactly as [`MerkleHasher::digest`] would.
+    ///
+    /// The default copies the parts into one buffer first. Hashers that can absorb their
+    /// input incrementally override it to feed each part in turn, so that combining two
+    /// nodes never allocates.
+    fn digest_parts(parts: &[&[u8]]) -> [u8; N] {
+        Self::digest(&parts.concat())
+    }
+}
+
+/// Keccak-256, the hash used by Ethereum and the default for [`crate::MerkleTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Keccak256;
+
+impl MerkleHasher for Keccak256 {
+    const ALGO: HashAlgo = HashAlgo::Keccak256;
+
+    fn digest(data: &[u8]) -> [u8; 32] {
+        keccak(&[data])
+    }
+
+    fn digest_parts(parts: &[&[u8]]) -> [u8; 32] {
+        keccak(parts)
+    }
+}
+
+/// Keccak-512, with the original Keccak padding like [`Keccak256`], for trees of 64-byte nodes
+/// such as [`crate::MerkleTree512`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Keccak512;
+
+impl MerkleHasher<64> for Keccak512 {
+    const ALGO: HashAlgo = HashAlgo::Keccak512;
+
+    fn digest(data: &[u8]) -> [u8; 64] {
+        Self::digest_parts(&[data])
+    }
+
+    fn digest_parts(parts: &[&[u8]]) -> [u8; 64] {
+        let mut keccak = tiny_keccak::Keccak::v512();
+        let mut hash = [0u8; 64];
+        parts.iter().for_each(|part| keccak.update(part));
+        keccak.finalize(&mut hash);
+        hash
+    }
+}
+
+/// Keccak-256 truncated to its first `N` bytes, for trees with nodes narrower than 32 bytes
+/// (e.g. 20-byte, address-sized roots). `N` must be at most 32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TruncatedKeccak256;
+
+impl<const N: usize> MerkleHasher<N> for TruncatedKeccak256 {
+    fn digest(data: &[u8]) -> [u8; N] {
+        Self::digest_parts(&[data])
+    }
+
+    fn digest_parts(parts: &[&[u8]]) -> [u8; N] {
+        const {
+            assert!(
+                N <= 32,
+                "Keccak-256 can't be truncated to more than 32 bytes"
+            )
+        };
+        keccak(parts)
+    }
+}
+
+/// SHA-256, as used by Bitcoin and Certificate Transparency. Requires the `sha2` feature.
+#[cfg(feature = "sha2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sha256;
+
+#[cfg(feature = "sha2")]
+impl MerkleHasher for Sha256 {
+    const ALGO: HashAlgo = HashAlgo::Sha256;
+
+    fn digest(data: &[u8]) -> [u8; 32] {
+        use sha2::Digest;
+        sha2::Sha256::digest(data).into()
+    }
+
+    fn digest_parts(parts: &[&[u8]]) -> [u8; 32] {
+        use sha2::Digest;
+        let mut hasher = sha2::Sha256::new();
+        parts.iter().for_each(|part| hasher.update(part));
+        hasher.finalize().into()
+    }
+}
+
+/// SHA-256 applied twice, `SHA-256(SHA-256(data))`, as Bitcoin hashes its Merkle nodes.
+/// Requires the `sha2` feature.
+#[cfg(feature = "sha2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DoubleSha256;
+
+#[cfg(feature = "sha2")]
+impl MerkleHasher for DoubleSha256 {
+    fn digest(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(&Sha256::digest(data))
+    }
+
+    fn digest_parts(parts: &[&[u8]]) -> [u8; 32] {
+        Sha256::digest(&Sha256::digest_parts(parts))
+    }
+}
+
+/// BLAKE3 with its default 32-byte output. Requires the `blake3` feature.
+#[cfg(feature = "blake3")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Blake3;
+
+#[cfg(feature = "blake3")]
+impl MerkleHasher for Blake3 {
+    const ALGO: HashAlgo = HashAlgo::Blake3;
+
+    fn digest(data: &[u8]) -> [u8; 32] {
+        blake3::hash(data).into()
+    }
+
+    fn digest_parts(parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        parts.iter().for_each(|part| {
+            hasher.update(part);
+        });
+        hasher.finalize().into()
+    }
+}
+
+// Squeezing fewer than 32 bytes out of the sponge yields a prefix of the full digest
+fn keccak<const N: usize>(parts: &[&[u8]]) -> [u8; N] {
+    let mut keccak = tiny_keccak::Keccak::v256();
+    let mut hash = [0u8; N];
+    parts.iter().for_each(|part| keccak.update(part));
+    keccak.finalize(&mut hash);
+    hash
+}
@@ -0,0 +1,159 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{decode_hex, GenericMerkleTree, MerkleError, MerkleHasher};
+
+#[derive(Serialize)]
+struct JsonTree<'a> {
+    leaves: &'a [String],
+    root: String,
+    levels: Vec<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParsedTree {
+    leaves: Vec<String>,
+    root: String,
+    levels: Vec<Vec<String>>,
+}
+
+impl<H: MerkleHasher<N>, const N: usize> GenericMerkleTree<String, H, N> {
+    /// Exports the tree as a JSON object for verifiers in other languages, e.g.
+    /// `{"leaves":["a","b"],"root":"0x805b…","levels":[["0x805b…"],["0x3ac2…","0xb555…"]]}`.
+    ///
+    /// The keys always appear in that order, with no whitespace. `leaves` holds the leaf
+    /// strings as they are, and `levels` every level's hashes, root first, so `levels[0][0]`
+    /// repeats `root` and the last level holds the leaf hashes. Every hash is written as `0x`
+    /// followed by exactly `2 * N` lowercase hex digits, the most significant byte first, which
+    /// is what ethers and viem expect of a `bytes32`.
+    ///
+    /// The format only records the leaves and hashes, not how the tree was built, so only a
+    /// tree built the default way, with [`GenericMerkleTree::new`] or the like, round-trips
+    /// through [`GenericMerkleTree::from_json`]. A sorted-pair, tagged, salted or otherwise
+    /// customized tree still exports, but its hashes can't be checked by a verifier that
+    /// assumes the default hashing, and loading it back returns
+    /// [`MerkleError::InconsistentTree`].
+    pub fn to_json(&self) -> String {
+        let hex = |hash: &[u8; N]| format!("0x{}", hex::encode(hash));
+        let tree = JsonTree {
+            leaves: &self.leaves,
+            root: hex(&self.root_hash()),
+            levels: self
+                .full_levels()
+                .iter()
+                .map(|level| level.iter().map(hex).collect())
+                .collect(),
+        };
+        serde_json::to_string(&tree).expect("strings always serialize")
+    }
+
+    /// Loads a tree written by [`GenericMerkleTree::to_json`], rebuilding it from its leaves
+    /// the default way and checking the stored root and levels against it.
+    ///
+    /// Hashes are accepted in either case, with or without the `0x` prefix. Returns
+    /// [`MerkleError::MalformedJson`] if the input isn't an object of exactly that shape or a
+    /// hash isn't `N` bytes of hex, [`MerkleError::EmptyTree`] if there are no leaves, and
+    /// [`MerkleError::InconsistentTree`] if the root or any level doesn't match the leaves.
+    pub fn from_json(json: &str) -> Result<Self, MerkleError> {
+        let parsed: ParsedTree =
+            serde_json::from_str(json).map_err(|_| MerkleError::MalformedJson)?;
+        let decode = |hash: &String| -> Result<[u8; N], MerkleError> {
+            decode_hex(hash.as_bytes())
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(MerkleError::MalformedJson)
+        };
+        let root = decode(&parsed.root)?;
+        let levels = parsed
+            .levels
+            .iter()
+            .map(|level| level.iter().map(decode).collect())
+            .collect::<Result<Vec<Vec<[u8; N]>>, _>>()?;
+
+        let tree = Self::try_new(parsed.leaves)?;
+        if root != tree.root_hash() || tree.hashes != levels {
+            return Err(MerkleError::InconsistentTree);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    #[test]
+    fn exact_format() {
+        let tree = MerkleTree::new(vec![String::from("a"), String::from("b")]);
+        let root = "0x805b21d846b189efaeb0377d6bb0d201b3872a363e607c25088f025b0c6ae1f8";
+        let a = "0x3ac225168df54212a25c1c01fd35bebfea408fdac2e31ddd6f80a4bbf9a5f1cb";
+        let b = "0xb5553de315e0edf504d9150af82dafa5c4667fa618ed0a6f19c69b41166c5510";
+        assert_eq!(
+            tree.to_json(),
+            format!(
+                r#"{{"leaves":["a","b"],"root":"{}","levels":[["{}"],["{}","{}"]]}}"#,
+                root, root, a, b
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut tree: MerkleTree = (0..5).map(|i| i.to_string()).collect();
+        let json = tree.to_json();
+        assert_eq!(MerkleTree::from_json(&json), Ok(tree.clone()));
+        let root = tree.root_hex();
+        let bare = json.replace("0x", "").replace(&root, &root.to_uppercase());
+        assert_eq!(MerkleTree::from_json(&bare), Ok(tree.clone()));
+
+        tree.prune();
+        assert_eq!(tree.to_json(), json);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let json = MerkleTree::new(vec![String::from("a"), String::from("b")]).to_json();
+
+        let tampered = json.replacen("0x3ac2", "0x3ac3", 1);
+        assert_eq!(
+            MerkleTree::from_json(&tampered),
+            Err(MerkleError::InconsistentTree)
+        );
+        let wrong_root = json.replacen("0x805b", "0x805c", 1);
+        assert_eq!(
+            MerkleTree::from_json(&wrong_root),
+            Err(MerkleError::InconsistentTree)
+        );
+
+        for malformed in [
+            "",
+            "[]",
+            r#"{"leaves":["a"],"root":"0x00"}"#,
+            r#"{"leaves":["a"],"root":"0x00","levels":[["0x00"]]}"#,
+            &json.replacen('}', r#","extra":1}"#, 1),
+        ] {
+            assert_eq!(
+                MerkleTree::from_json(malformed),
+                Err(MerkleError::MalformedJson),
+                "{}",
+                malformed
+            );
+        }
+        let empty = format!(
+            r#"{{"leaves":[],"root":"0x{}","levels":[]}}"#,
+            "00".repeat(32)
+        );
+        assert_eq!(MerkleTree::from_json(&empty), Err(MerkleError::EmptyTree));
+
+        // Only the default scheme is rebuilt on loading
+        let tagged = MerkleTree::new_tagged(vec![String::from("a"), String::from("b")], 0, 1);
+        assert_eq!(
+            MerkleTree::from_json(&tagged.to_json()),
+            Err(MerkleError::InconsistentTree)
+        );
+    }
+}
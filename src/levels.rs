@@ -0,0 +1,369 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Index, IndexMut};
+
+/// Every level of a tree's hashes in a single contiguous buffer.
+///
+/// Levels are indexed root-first like the nested `Vec<Vec<_>>` that [`crate::build`] returns,
+/// but are laid out leaves-first, each in its own region of the buffer. The regions are sized
+/// for a leaf capacity that grows by doubling, like a `Vec`, so appending leaves doesn't move
+/// the levels above them.
+#[derive(Clone)]
+pub(crate) struct Levels<const N: usize> {
+    nodes: Vec<[u8; N]>,
+    // Where each level's region starts, leaves first; the leaf region is the leaf capacity
+    starts: Vec<usize>,
+    // How many nodes of each level's region are in use, leaves first
+    widths: Vec<usize>,
+}
+
+impl<const N: usize> Levels<N> {
+    /// Builds every level above `leaf_hashes`, hashing each pair with `parent`.
+    ///
+    /// `parent(level, index)` returns the parent of the even-indexed node `level[index]`.
+    pub(crate) fn build(
+        leaf_hashes: Vec<[u8; N]>,
+        parent: impl Fn(&[[u8; N]], usize) -> [u8; N],
+    ) -> Self {
+        let mut levels = Levels {
+            starts: vec![0],
+            widths: vec![leaf_hashes.len()],
+            nodes: leaf_hashes,
+        };
+        if levels.nodes.is_empty() {
+            levels.widths.clear();
+            return levels;
+        }
+        let leaf_count = levels.nodes.len();
+        levels
+            .nodes
+            .reserve_exact(Self::node_count(leaf_count).map_or(0, |total| total - leaf_count));
+
+        // The buffer is exactly full, so each new level can simply be pushed onto the end
+        while let Some(&width) = levels.widths.last().filter(|width| **width > 1) {
+            let start = levels.starts[levels.starts.len() - 1];
+            levels.starts.push(levels.nodes.len());
+            levels.widths.push(width.div_ceil(2));
+            for index in (0..width).step_by(2) {
+                let node = parent(&levels.nodes[start..start + width], index);
+                levels.nodes.push(node);
+            }
+        }
+        levels
+    }
+
+    /// Takes already-computed levels in the nested root-first form, each parent consistent
+    /// with its children, without rehashing anything.
+    pub(crate) fn from_nested(nested: Vec<Vec<[u8; N]>>) -> Self {
+        let mut levels = Levels {
+            nodes: Vec::with_capacity(nested.iter().map(Vec::len).sum()),
+            starts: vec![],
+            widths: vec![],
+        };
+        for level in nested.iter().rev() {
+            levels.starts.push(levels.nodes.len());
+            levels.widths.push(level.len());
+            levels.nodes.extend_from_slice(level);
+        }
+        levels
+    }
+
+    /// The number of nodes in every level of a tree over `leaf_count` leaves, leaves included,
+    /// or `None` if that doesn't fit in a `usize`, as for more than [`crate::MAX_LEAVES`].
+    ///
+    /// Collecting the leaf hashes into a `Vec` with this capacity lets [`Levels::build`] add
+    /// the levels above them without reallocating.
+    pub(crate) fn node_count(leaf_count: usize) -> Option<usize> {
+        let mut total = leaf_count;
+        let mut width = leaf_count;
+        while width > 1 {
+            width = width.div_ceil(2);
+            total = total.checked_add(width)?;
+        }
+        Some(total)
+    }
+
+    /// Appends `leaf_hashes` to the leaf level and recomputes every node that depends on them.
+    pub(crate) fn append(
+        &mut self,
+        leaf_hashes: &[[u8; N]],
+        parent: impl Fn(&[[u8; N]], usize) -> [u8; N],
+    ) {
+        if leaf_hashes.is_empty() {
+            return;
+        }
+        if self.widths.is_empty() {
+            *self = Levels::build(leaf_hashes.to_vec(), parent);
+            return;
+        }
+
+        let old_width = self.widths[0];
+        self.reserve(old_width + leaf_hashes.len());
+        self.nodes[old_width..old_width + leaf_hashes.len()].copy_from_slice(leaf_hashes);
+        self.widths[0] += leaf_hashes.len();
+
+        // Only parents from the first appended node's onward can have changed
+        let mut first_changed = old_width;
+        let mut level = 0;
+        while self.widths[level] > 1 {
+            let width = self.widths[level].div_ceil(2);
+            if self.widths.len() == level + 1 {
+                self.widths.push(0);
+            }
+            for index in first_changed / 2..width {
+                let node = parent(self.region(level), index * 2);
+                self.nodes[self.starts[level + 1] + index] = node;
+            }
+            self.widths[level + 1] = width;
+            first_changed /= 2;
+            level += 1;
+        }
+    }
+
+    /// Keeps only the first `leaf_count` leaves, which must be at least one, and recomputes
+    /// the right edge of every level above them.
+    pub(crate) fn truncate(
+        &mut self,
+        leaf_count: usize,
+        parent: impl Fn(&[[u8; N]], usize) -> [u8; N],
+    ) {
+        self.widths.truncate(1);
+        self.widths[0] = leaf_count;
+        // Each level's last node is the only one that can have lost a child; the regions
+        // keep their capacity for later appends
+        let mut level = 0;
+        while self.widths[level] > 1 {
+            let width = self.widths[level];
+            self.widths.push(width.div_ceil(2));
+            let node = parent(self.region(level), (width - 1) & !1);
+            self.nodes[self.starts[level + 1] + width.div_ceil(2) - 1] = node;
+            level += 1;
+        }
+    }
+
+    /// Empties the levels, handing back their buffer cleared so a rebuild can reuse its
+    /// allocation.
+    pub(crate) fn take_buffer(&mut self) -> Vec<[u8; N]> {
+        self.starts.clear();
+        self.widths.clear();
+        let mut nodes = core::mem::take(&mut self.nodes);
+        nodes.clear();
+        nodes
+    }
+
+    /// Drops every level above the leaves and frees the memory they took, keeping the leaf
+    /// hashes as the only level.
+    pub(crate) fn prune(&mut self) {
+        let leaf_count = self.widths.first().copied().unwrap_or(0);
+        self.nodes.truncate(leaf_count);
+        self.nodes.shrink_to_fit();
+        self.starts.truncate(1);
+        self.widths.truncate(1);
+    }
+
+    /// Empties pruned levels, handing back their leaf hashes to rebuild the levels above from.
+    pub(crate) fn take_leaf_hashes(&mut self) -> Vec<[u8; N]> {
+        debug_assert!(
+            self.len() <= 1,
+            "only a pruned tree's levels hold just the leaves"
+        );
+        self.starts.clear();
+        self.widths.clear();
+        core::mem::take(&mut self.nodes)
+    }
+
+    /// The number of levels, counting both the root and the leaves.
+    pub(crate) fn len(&self) -> usize {
+        self.widths.len()
+    }
+
+    pub(crate) fn get(&self, level: usize) -> Option<&[[u8; N]]> {
+        (level < self.len()).then(|| &self[level])
+    }
+
+    pub(crate) fn last(&self) -> Option<&[[u8; N]]> {
+        self.len().checked_sub(1).map(|level| &self[level])
+    }
+
+    /// The levels from the root down to the leaves.
+    pub(crate) fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &[[u8; N]]> + ExactSizeIterator + '_ {
+        (0..self.len()).map(|level| &self[level])
+    }
+
+    /// Copies the levels out into the nested root-first form returned by [`crate::build`].
+    pub(crate) fn to_nested(&self) -> Vec<Vec<[u8; N]>> {
+        self.iter().map(<[_]>::to_vec).collect()
+    }
+
+    // The in-use part of a level's region, counting leaves-first
+    fn region(&self, level: usize) -> &[[u8; N]] {
+        let start = self.starts[level];
+        &self.nodes[start..start + self.widths[level]]
+    }
+
+    // Makes room for `leaf_count` leaves, moving every level into a larger buffer if needed
+    fn reserve(&mut self, leaf_count: usize) {
+        let capacity = self.starts.get(1).copied().unwrap_or(self.nodes.len());
+        if leaf_count <= capacity {
+            return;
+        }
+
+        // Doubling stops at the largest capacity whose levels still fit in the buffer
+        let capacity = leaf_count.max(capacity.saturating_mul(2).min(crate::MAX_LEAVES));
+        let mut starts = vec![0];
+        let mut region = capacity;
+        let mut total = capacity;
+        while region > 1 {
+            region = region.div_ceil(2);
+            starts.push(total);
+            total += region;
+        }
+
+        let mut nodes = vec![[0u8; N]; total];
+        for (level, start) in starts.iter().enumerate().take(self.len()) {
+            nodes[*start..*start + self.widths[level]].copy_from_slice(self.region(level));
+        }
+        self.nodes = nodes;
+        self.starts = starts;
+    }
+}
+
+impl<const N: usize> Index<usize> for Levels<N> {
+    type Output = [[u8; N]];
+
+    fn index(&self, level: usize) -> &[[u8; N]] {
+        self.region(self.len() - 1 - level)
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for Levels<N> {
+    fn index_mut(&mut self, level: usize) -> &mut [[u8; N]] {
+        let level = self.len() - 1 - level;
+        let start = self.starts[level];
+        &mut self.nodes[start..start + self.widths[level]]
+    }
+}
+
+// Unused capacity doesn't take part in comparisons or formatting
+impl<const N: usize> PartialEq for Levels<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<const N: usize> Eq for Levels<N> {}
+
+impl<const N: usize> PartialEq<Vec<Vec<[u8; N]>>> for Levels<N> {
+    fn eq(&self, other: &Vec<Vec<[u8; N]>>) -> bool {
+        self.iter().eq(other.iter().map(Vec::as_slice))
+    }
+}
+
+impl<const N: usize> PartialEq<Levels<N>> for Vec<Vec<[u8; N]>> {
+    fn eq(&self, other: &Levels<N>) -> bool {
+        other == self
+    }
+}
+
+impl<const N: usize> fmt::Debug for Levels<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(HexLevel)).finish()
+    }
+}
+
+/// Formats a node as bare lowercase hex for `Debug` output.
+pub(crate) struct HexNode<'a, const N: usize>(pub(crate) &'a [u8; N]);
+
+impl<const N: usize> fmt::Debug for HexNode<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+struct HexLevel<'a, const N: usize>(&'a [[u8; N]]);
+
+impl<const N: usize> fmt::Debug for HexLevel<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.0.iter().map(HexNode)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A stand-in for hashing: the parent of a pair is their bytewise sum
+    fn parent(level: &[[u8; 1]], index: usize) -> [u8; 1] {
+        let right = level.get(index + 1).unwrap_or(&level[index]);
+        [level[index][0].wrapping_add(right[0])]
+    }
+
+    fn leaves(n: u8) -> Vec<[u8; 1]> {
+        (1..=n).map(|i| [i]).collect()
+    }
+
+    #[test]
+    fn root_first_indexing() {
+        let levels = Levels::build(leaves(5), parent);
+        assert_eq!(
+            levels,
+            vec![
+                vec![[30]],
+                vec![[10], [20]],
+                vec![[3], [7], [10]],
+                vec![[1], [2], [3], [4], [5]],
+            ]
+        );
+        assert_eq!(levels.last(), Some(&leaves(5)[..]));
+        assert!(levels.get(4).is_none());
+    }
+
+    #[test]
+    fn debug_as_hex() {
+        let levels = Levels::build(vec![[0xab], [0x01]], parent);
+        assert_eq!(format!("{:?}", levels), "[[ac], [ab, 01]]");
+    }
+
+    #[test]
+    fn append_matches_build() {
+        for (initial, added) in [(1, 1), (1, 8), (3, 1), (4, 5), (7, 9), (8, 1)] {
+            let mut levels = Levels::build(leaves(initial), parent);
+            levels.append(&leaves(initial + added)[initial as usize..], parent);
+            assert_eq!(levels, Levels::build(leaves(initial + added), parent));
+        }
+
+        let mut levels = Levels::from_nested(Levels::build(leaves(3), parent).to_nested());
+        levels.append(&leaves(9)[3..], parent);
+        assert_eq!(levels, Levels::build(leaves(9), parent));
+
+        let mut levels = Levels::build(vec![], parent);
+        assert_eq!(levels.len(), 0);
+        levels.append(&leaves(3), parent);
+        assert_eq!(levels, Levels::build(leaves(3), parent));
+    }
+
+    #[test]
+    fn truncate_matches_build() {
+        for (initial, kept) in [(2, 1), (5, 4), (9, 8), (9, 5), (16, 3)] {
+            let mut levels = Levels::build(leaves(initial), parent);
+            levels.truncate(kept as usize, parent);
+            assert_eq!(levels, Levels::build(leaves(kept), parent));
+
+            // The freed capacity is reused by later appends
+            levels.append(&leaves(initial)[kept as usize..], parent);
+            assert_eq!(levels, Levels::build(leaves(initial), parent));
+        }
+    }
+
+    #[test]
+    fn prune_keeps_leaves() {
+        let mut levels = Levels::build(leaves(5), parent);
+        levels.append(&[[6]], parent);
+        levels.prune();
+        assert_eq!(levels, vec![leaves(6)]);
+        assert_eq!(levels.nodes.capacity(), 6);
+    }
+}
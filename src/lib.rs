@@ -1,266 +1,3914 @@
-use tiny_keccak::Hasher;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-fn keccak(data: &[u8]) -> [u8; 32] {
-    let mut keccak = tiny_keccak::Keccak::v256();
-    let mut hash = [0u8; 32];
-    keccak.update(data);
-    keccak.finalize(&mut hash);
-    hash
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use levels::{HexNode, Levels};
+
+mod absence;
+#[cfg(feature = "sha2")]
+mod bitcoin;
+mod builder;
+mod bytes;
+mod cache;
+mod consistency;
+mod dot;
+mod error;
+mod frontier;
+#[cfg(test)]
+mod golden;
+mod hasher;
+#[cfg(feature = "json")]
+mod json;
+mod levels;
+mod multiproof;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod patricia;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod sparse;
+
+pub use absence::AbsenceProof;
+#[cfg(feature = "sha2")]
+pub use bitcoin::{build_bitcoin, build_bitcoin_checked};
+pub use builder::{GenericMerkleBuilder, MerkleBuilder};
+pub use cache::{build_with_cache, LeafCache};
+pub use consistency::verify_consistency;
+pub use error::MerkleError;
+pub use frontier::{Frontier, GenericFrontier};
+#[cfg(feature = "blake3")]
+pub use hasher::Blake3;
+#[cfg(feature = "sha2")]
+pub use hasher::{DoubleSha256, Sha256};
+pub use hasher::{HashAlgo, Keccak256, Keccak512, MerkleHasher, TruncatedKeccak256};
+pub use multiproof::{verify_multiproof, verify_range_proof, MultiProof, RangeProof};
+#[cfg(feature = "rayon")]
+pub use parallel::build_parallel;
+pub use patricia::PatriciaTrie;
+pub use sparse::{verify_sparse_proof, SparseMerkleTree, SparseProof};
+
+/// A value that can be stored as a leaf of a [`MerkleTree`].
+pub trait Hashable {
+    /// The bytes that get hashed to form the leaf hash.
+    fn hash_bytes(&self) -> Vec<u8>;
+
+    /// How the leaf is shown by the tree's `Display` impl. Defaults to the hex of
+    /// [`Hashable::hash_bytes`].
+    fn display_leaf(&self) -> String {
+        hex::encode(self.hash_bytes())
+    }
+}
+
+impl Hashable for str {
+    fn hash_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn display_leaf(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Hashable for String {
+    fn hash_bytes(&self) -> Vec<u8> {
+        self.as_str().hash_bytes()
+    }
+
+    fn display_leaf(&self) -> String {
+        self.clone()
+    }
+}
+
+impl Hashable for [u8] {
+    fn hash_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl Hashable for Vec<u8> {
+    fn hash_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl<const N: usize> Hashable for [u8; N] {
+    fn hash_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl<T: Hashable + ?Sized> Hashable for &T {
+    fn hash_bytes(&self) -> Vec<u8> {
+        (**self).hash_bytes()
+    }
+
+    fn display_leaf(&self) -> String {
+        (**self).display_leaf()
+    }
+}
+
+/// A leaf value paired with metadata, such as the amount owed to each address of a claim
+/// tree. Only the value is hashed, so the metadata never changes the tree's hashes and isn't
+/// covered by proofs. [`GenericMerkleTree::leaf_meta`] reads it back.
+impl<L: Hashable, M> Hashable for (L, M) {
+    fn hash_bytes(&self) -> Vec<u8> {
+        self.0.hash_bytes()
+    }
+
+    fn display_leaf(&self) -> String {
+        self.0.display_leaf()
+    }
+}
+
+/// A Merkle tree over leaves of type `T`, hashed with `H` into `N`-byte nodes.
+///
+/// Most code should use the [`MerkleTree`] alias, which fixes the hasher to [`Keccak256`].
+///
+/// Cloning copies every leaf and every level of hashes, roughly `2 * leaf_count` nodes.
+///
+/// The tree is `Send` and `Sync` whenever `T` is, whatever the hasher, and every read-only
+/// method takes `&self` without any interior mutability. A tree behind an `Arc` can serve
+/// proofs and roots to any number of threads at once.
+pub struct GenericMerkleTree<T, H, const N: usize = 32> {
+    leaves: Vec<T>,
+    // Indexed root-first: `hashes[0]` holds just the root and the last level the leaf hashes
+    hashes: Levels<N>,
+    // Always `hashes[0][0]`, kept alongside so reading it never touches `hashes`
+    root: [u8; N],
+    scheme: Scheme,
+    // Set by `freeze`, after which every mutation fails
+    frozen: bool,
+    // Set by `prune`, while `hashes` holds only the leaf level
+    pruned: bool,
+    // Set by `leaves_mut` until `rebuild_in_place`, while `hashes` may not match `leaves`
+    stale: bool,
+    hasher: PhantomData<fn() -> H>,
+}
+
+/// How a tree combines its nodes, fixed when the tree is built. Proof verification against the
+/// tree has to use the same scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Scheme {
+    /// Order each pair as `min || max` before hashing, as OpenZeppelin's `MerkleProof` expects.
+    sorted_pairs: bool,
+    odd: OddStrategy,
+    /// Domain-separation bytes prepended to leaf data and to node concatenations respectively.
+    tags: Option<(u8, u8)>,
+    /// Leaves are already hashes and are used as the leaf level as-is.
+    prehashed: bool,
+    /// Lookups on a prehashed tree take the original value and hash it the default way, as
+    /// [`GenericMerkleBuilder`] hashed the leaves, rather than taking a leaf hash.
+    #[cfg_attr(feature = "serde", serde(default))]
+    value_lookups: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    encoding: LeafEncoding,
+    #[cfg_attr(feature = "serde", serde(default))]
+    node_encoding: NodeEncoding,
+    /// Bytes prepended to every leaf's data, after any leaf tag.
+    #[cfg_attr(feature = "serde", serde(default))]
+    salt: Option<[u8; 32]>,
+}
+
+/// How a leaf's [`Hashable::hash_bytes`] are turned into the bytes that get hashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LeafEncoding {
+    /// Hash the bytes as they are, e.g. the UTF-8 of a string leaf.
+    #[default]
+    Utf8,
+    /// Treat the bytes as hex text, with an optional `0x` prefix, and hash the decoded bytes.
+    Hex,
+}
+
+/// How two child hashes are joined into the bytes their parent hashes.
+///
+/// Any node tag (see [`build_tagged`]) still comes first, before the encoded children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeEncoding {
+    /// `left || right`.
+    #[default]
+    Raw,
+    /// `left || separator || right`.
+    Separator(u8),
+    /// Each child preceded by its length in bytes as a big-endian `u32`:
+    /// `len(left) || left || len(right) || right`.
+    LengthPrefixed,
+}
+
+/// What the last node of a level with an odd number of nodes gets paired with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OddStrategy {
+    /// Hash the node with itself (`node || node`).
+    #[default]
+    DuplicateLast,
+    /// Hash the node with an all-zero sentinel (`node || 0x00..00`).
+    PadZero,
+}
+
+/// The order two child hashes are joined in before hashing their parent, as reported by
+/// [`GenericMerkleTree::pair_order`], which decides how a verifier has to fold a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PairOrder {
+    /// `left || right`, by position, so each proof step needs to know which side its sibling
+    /// is on, as in [`verify_proof`].
+    Positional,
+    /// `min || max` of the two hashes whatever their positions, as in OpenZeppelin's
+    /// `MerkleProof`, so proofs need no directions.
+    Sorted,
+}
+
+/// How [`GenericMerkleTree::new_sorted_leaves`] orders leaves before building.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeafOrder {
+    /// Lexicographically by [`Hashable::hash_bytes`], which for strings is their usual order.
+    #[default]
+    Bytes,
+    /// By leaf hash, as OpenZeppelin's `StandardMerkleTree` orders its leaves.
+    Hash,
+}
+
+impl Scheme {
+    fn hash_leaf<H: MerkleHasher<N>, L: Hashable + ?Sized, const N: usize>(
+        &self,
+        leaf: &L,
+    ) -> [u8; N] {
+        if self.prehashed {
+            // Every prehashed tree has `[u8; N]` leaves, so this only hashes a leaf of another
+            // type deserialized with a prehashed scheme, rather than panicking on it. Lookups
+            // don't come through here, see `lookup_hash`
+            let bytes = leaf.hash_bytes();
+            return bytes
+                .as_slice()
+                .try_into()
+                .unwrap_or_else(|_| H::digest(&bytes));
+        }
+
+        let mut bytes = leaf.hash_bytes();
+        if self.encoding == LeafEncoding::Hex {
+            // Like a non-hash in a prehashed tree, invalid hex is hashed as is and won't be found
+            bytes = decode_hex(&bytes).unwrap_or(bytes);
+        }
+        let tag = self.tags.map(|(leaf_tag, _)| [leaf_tag]);
+        let tag: &[u8] = tag.as_ref().map_or(&[], |tag| tag);
+        let salt: &[u8] = self.salt.as_ref().map_or(&[], |salt| salt);
+        H::digest_parts(&[tag, salt, &bytes])
+    }
+
+    // The leaf hash a lookup for `value` matches, or `None` if it can't match any leaf: a
+    // prehashed tree is looked up by leaf hash, so only an `N`-byte value is one, unless it
+    // came from a builder
+    fn lookup_hash<H: MerkleHasher<N>, L: Hashable + ?Sized, const N: usize>(
+        &self,
+        value: &L,
+    ) -> Option<[u8; N]> {
+        match (self.prehashed, self.value_lookups) {
+            (true, true) => Some(Scheme::default().hash_leaf::<H, L, N>(value)),
+            (true, false) => value.hash_bytes().as_slice().try_into().ok(),
+            (false, _) => Some(self.hash_leaf::<H, L, N>(value)),
+        }
+    }
+
+    fn hash_nodes<H: MerkleHasher<N>, const N: usize>(
+        &self,
+        left: &[u8; N],
+        right: &[u8; N],
+    ) -> [u8; N] {
+        let (left, right) = if self.sorted_pairs && right < left {
+            (right, left)
+        } else {
+            (left, right)
+        };
+        // The crate's own hashers take the parts one by one, so hashing a node never allocates
+        let tag = self.tags.map(|(_, node_tag)| [node_tag]);
+        let tag: &[u8] = tag.as_ref().map_or(&[], |tag| tag);
+        match self.node_encoding {
+            NodeEncoding::Raw => H::digest_parts(&[tag, left, right]),
+            NodeEncoding::Separator(separator) => {
+                H::digest_parts(&[tag, left, &[separator], right])
+            }
+            NodeEncoding::LengthPrefixed => {
+                let prefix = (N as u32).to_be_bytes();
+                H::digest_parts(&[tag, &prefix, left, &prefix, right])
+            }
+        }
+    }
+
+    // The parent of the even-indexed node `level[index]` and its sibling
+    fn parent<H: MerkleHasher<N>, const N: usize>(
+        &self,
+        level: &[[u8; N]],
+        index: usize,
+    ) -> [u8; N] {
+        self.hash_nodes::<H, N>(&level[index], &self.sibling(level, index))
+    }
+
+    // The node `level[index]` is hashed with, padding out the last node of an odd level
+    fn sibling<const N: usize>(&self, level: &[[u8; N]], index: usize) -> [u8; N] {
+        match level.get(index ^ 1) {
+            Some(sibling) => *sibling,
+            None => self.odd_sibling(&level[index]),
+        }
+    }
+
+    // The right-hand partner of the last node on an odd level
+    fn odd_sibling<const N: usize>(&self, node: &[u8; N]) -> [u8; N] {
+        match self.odd {
+            OddStrategy::DuplicateLast => *node,
+            OddStrategy::PadZero => [0u8; N],
+        }
+    }
+}
+
+// Checks that a tree of `len` leaves doesn't exceed `MAX_LEAVES`
+fn check_leaf_count(len: usize) -> Result<(), MerkleError> {
+    match len > MAX_LEAVES {
+        true => Err(MerkleError::TooManyLeaves {
+            len,
+            max: MAX_LEAVES,
+        }),
+        false => Ok(()),
+    }
+}
+
+// Decodes hex text, ignoring a leading `0x` or `0X`
+fn decode_hex(text: &[u8]) -> Option<Vec<u8>> {
+    let digits = text
+        .strip_prefix(b"0x")
+        .or_else(|| text.strip_prefix(b"0X"))
+        .unwrap_or(text);
+    hex::decode(digits).ok()
+}
+
+impl<H: MerkleHasher<N>, const N: usize> GenericMerkleTree<[u8; N], H, N> {
+    /// Builds a tree whose leaf level is `leaf_hashes` itself, see [`build_from_hashes`].
+    ///
+    /// The tree's leaves are the hashes, so lookups and proofs take a hash in place of a leaf
+    /// value. A value passed to [`GenericMerkleTree::contains`] or
+    /// [`GenericMerkleTree::index_of`] is compared as a leaf hash, never hashed first, so one
+    /// that isn't `N` bytes long matches nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaf_hashes` is empty.
+    pub fn from_hashes(leaf_hashes: Vec<[u8; N]>) -> Self {
+        let scheme = Scheme {
+            prehashed: true,
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaf_hashes, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Like [`GenericMerkleTree::from_hashes`], but with each leaf hash given as hex, with or
+    /// without a `0x` prefix.
+    ///
+    /// Returns [`MerkleError::InvalidHex`] or [`MerkleError::InvalidHashLength`] for the first
+    /// string that isn't a hex-encoded `N`-byte hash, or [`MerkleError::EmptyTree`] if there
+    /// are none.
+    pub fn from_hex_leaves(hexes: Vec<String>) -> Result<Self, MerkleError> {
+        let mut leaf_hashes = Vec::with_capacity(hexes.len());
+        for (index, hex) in hexes.iter().enumerate() {
+            let bytes = decode_hex(hex.as_bytes()).ok_or(MerkleError::InvalidHex { index })?;
+            let hash = bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| MerkleError::InvalidHashLength {
+                    index,
+                    len: bytes.len(),
+                    expected: N,
+                })?;
+            leaf_hashes.push(hash);
+        }
+        let scheme = Scheme {
+            prehashed: true,
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaf_hashes, scheme)
+    }
+}
+
+impl<'a, H: MerkleHasher<N>, const N: usize> GenericMerkleTree<&'a [u8], H, N> {
+    /// Builds a tree whose leaves are consecutive `chunk_size`-byte pieces of `data`, the last
+    /// of them possibly shorter, as in BitTorrent or IPFS-style content addressing.
+    ///
+    /// The leaves borrow from `data` rather than copying it. Data no longer than one chunk is
+    /// a single leaf, so the root is the hash of the whole of it, and empty data returns
+    /// [`MerkleError::EmptyTree`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn from_chunks(data: &'a [u8], chunk_size: usize) -> Result<Self, MerkleError> {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        Self::try_new(data.chunks(chunk_size).collect())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: MerkleHasher<N>, const N: usize> GenericMerkleTree<String, H, N> {
+    /// Builds a tree with one leaf per line of `reader`, without each line's `\n` or `\r\n`.
+    ///
+    /// Read errors, including invalid UTF-8, are returned as they are. Input with no lines is
+    /// an [`std::io::ErrorKind::InvalidInput`] error wrapping [`MerkleError::EmptyTree`].
+    pub fn from_lines(reader: impl std::io::BufRead) -> std::io::Result<Self> {
+        let leaves = reader.lines().collect::<std::io::Result<Vec<String>>>()?;
+        Self::try_new(leaves)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))
+    }
+}
+
+// Derived `Clone` would needlessly require `H: Clone`
+impl<T: Clone, H, const N: usize> Clone for GenericMerkleTree<T, H, N> {
+    fn clone(&self) -> Self {
+        GenericMerkleTree {
+            leaves: self.leaves.clone(),
+            hashes: self.hashes.clone(),
+            root: self.root,
+            scheme: self.scheme,
+            frozen: self.frozen,
+            pruned: self.pruned,
+            stale: self.stale,
+            hasher: PhantomData,
+        }
+    }
+}
+
+/// Trees are equal when they have the same leaves in the same order and the same hashes at
+/// every level (which also requires them to have been built the same way). Levels dropped by
+/// [`GenericMerkleTree::prune`] are left out, so pruning doesn't change what a tree equals.
+impl<T: PartialEq, H, const N: usize> PartialEq for GenericMerkleTree<T, H, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.leaves == other.leaves
+            && self.scheme == other.scheme
+            && self.depth() == other.depth()
+            && (0..self.depth()).all(|level| match (self.level(level), other.level(level)) {
+                (Some(ours), Some(theirs)) => ours == theirs,
+                _ => true,
+            })
+    }
+}
+
+impl<T: Eq, H, const N: usize> Eq for GenericMerkleTree<T, H, N> {}
+
+/// Hashes only the root, which commits to the leaves of any correctly built tree, so trees can
+/// be keyed or deduplicated by commitment in hash-based collections. Equal trees always have
+/// equal roots, while trees that share a root but differ in their leaves or scheme are merely
+/// a hash collision and still compare unequal.
+impl<T, H, const N: usize> core::hash::Hash for GenericMerkleTree<T, H, N> {
+    fn hash<S: core::hash::Hasher>(&self, state: &mut S) {
+        self.root.hash(state);
+    }
+}
+
+impl<T, H, const N: usize> GenericMerkleTree<T, H, N> {
+    /// The number of levels in the tree, counting both the root and the leaves.
+    pub fn depth(&self) -> usize {
+        match self.pruned {
+            true => height_for(self.hashed_leaf_count()),
+            false => self.hashes.len(),
+        }
+    }
+
+    // The number of leaf hashes stored, which differs from the number of leaves while they're
+    // being edited through `leaves_mut`
+    fn hashed_leaf_count(&self) -> usize {
+        self.hashes.last().map_or(0, <[_]>::len)
+    }
+
+    /// Whether [`GenericMerkleTree::prune`] has dropped the tree's inner levels.
+    pub fn is_pruned(&self) -> bool {
+        self.pruned
+    }
+
+    // The hashes of `level`, counted root-first, or `None` if the tree isn't that deep or the
+    // level was dropped by `prune`
+    fn level(&self, level: usize) -> Option<&[[u8; N]]> {
+        if !self.pruned {
+            return self.hashes.get(level);
+        }
+        match level {
+            0 => Some(core::slice::from_ref(&self.root)),
+            _ if level + 1 == self.depth() => self.hashes.last(),
+            _ => None,
+        }
+    }
+
+    // Every level the tree stores, root first, with its root-first number
+    fn stored_levels(&self) -> impl DoubleEndedIterator<Item = (usize, &[[u8; N]])> {
+        (0..self.depth()).filter_map(move |level| Some((level, self.level(level)?)))
+    }
+
+    // For operations that need the hashes to match the leaves
+    fn assert_fresh(&self) {
+        assert!(
+            !self.stale,
+            "the leaves were edited; rebuild the tree first"
+        );
+    }
+
+    // For operations that also need every level
+    fn assert_complete(&self) {
+        self.assert_fresh();
+        assert!(!self.pruned, "the tree is pruned; rehydrate it first");
+    }
+}
+
+/// Equivalent to [`GenericMerkleTree::from_hashes`], including its panic on empty input.
+impl<H: MerkleHasher<N>, const N: usize> From<Vec<[u8; N]>> for GenericMerkleTree<[u8; N], H, N> {
+    fn from(leaf_hashes: Vec<[u8; N]>) -> Self {
+        Self::from_hashes(leaf_hashes)
+    }
+}
+
+/// Collects the leaves and builds with [`GenericMerkleTree::new`], including its panic on
+/// empty input.
+impl<T: Hashable, H: MerkleHasher<N>, const N: usize> FromIterator<T>
+    for GenericMerkleTree<T, H, N>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(leaves: I) -> Self {
+        Self::new(leaves.into_iter().collect())
+    }
+}
+
+/// The most leaves a tree can have, as any more would need more hashes across all its levels
+/// than a `usize` can count.
+///
+/// Constructors and appends return [`MerkleError::TooManyLeaves`] rather than exceed it. On a
+/// 64-bit target no tree that fits in memory gets near it, but a 32-bit one can.
+pub const MAX_LEAVES: usize = usize::MAX / 2 + 1;
+
+/// The longest proof [`verify_proof`] will accept: one sibling per level of a tree with
+/// `usize::MAX` leaves.
+pub const MAX_PROOF_LEN: usize = usize::BITS as usize;
+
+// Keeps the thread-safety promised in `GenericMerkleTree`'s docs from silently regressing
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<GenericMerkleTree<String, *const (), 32>>();
+};
+
+/// A Keccak-256 Merkle tree, the crate's default.
+pub type MerkleTree<T = String> = GenericMerkleTree<T, Keccak256>;
+
+/// A Keccak-512 Merkle tree, whose nodes and root are 64 bytes wide.
+pub type MerkleTree512<T = String> = GenericMerkleTree<T, Keccak512, 64>;
+
+/// A self-describing inclusion proof from [`GenericMerkleTree::get_proof`].
+///
+/// Rather than a direction flag per level, the proof carries the leaf's position, and the side
+/// each sibling is on follows from it: at height `h` above the leaves the sibling is on the
+/// left when bit `h` of `leaf_index` is set. So the directions can't disagree with the
+/// siblings, and the proof serializes as plain integers and hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof<const N: usize = 32> {
+    pub leaf_index: usize,
+    /// The number of leaves in the tree the proof was taken from.
+    pub leaf_count: usize,
+    /// The sibling hashes, ordered from the leaf level upward.
+    pub siblings: Vec<[u8; N]>,
 }
 
-#[derive(Debug)]
-pub struct MerkleTree {
-    leaves: Vec<String>,
-    hashes: Vec<Vec<[u8; 32]>>,
-}
+impl<T: Hashable, H: MerkleHasher<N>, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Builds a tree over `leaves`.
+    ///
+    /// A single leaf is its own root: the tree has one level, and the root is the leaf hash
+    /// itself rather than the leaf hashed with a copy of itself. Odd nodes are only ever
+    /// paired on levels that have a neighbour to pair them with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty; use [`GenericMerkleTree::try_new`] for untrusted input.
+    #[allow(clippy::new_without_default)]
+    pub fn new(leaves: Vec<T>) -> Self {
+        Self::try_new(leaves).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree over `leaves`, returning [`MerkleError::EmptyTree`] if there are none.
+    pub fn try_new(leaves: Vec<T>) -> Result<Self, MerkleError> {
+        Self::with_scheme(leaves, Scheme::default())
+    }
+
+    /// Builds a tree over `leaves` unless there are more than `max_leaves` of them, for
+    /// services that build trees from untrusted input and need a hard cap on the work.
+    ///
+    /// Returns [`MerkleError::TooManyLeaves`] before hashing anything if the cap is exceeded,
+    /// or [`MerkleError::EmptyTree`] if there are no leaves.
+    pub fn new_bounded(leaves: Vec<T>, max_leaves: usize) -> Result<Self, MerkleError> {
+        if leaves.len() > max_leaves {
+            return Err(MerkleError::TooManyLeaves {
+                len: leaves.len(),
+                max: max_leaves,
+            });
+        }
+        Self::try_new(leaves)
+    }
+
+    /// Builds a tree over `leaves`, requiring their count to be a power of two so that every
+    /// level pairs up evenly and no node is ever duplicated.
+    ///
+    /// Returns [`MerkleError::NotPowerOfTwo`] for any other count, or
+    /// [`MerkleError::EmptyTree`] if there are no leaves. Otherwise the tree is exactly the one
+    /// [`GenericMerkleTree::new`] builds.
+    pub fn new_balanced(leaves: Vec<T>) -> Result<Self, MerkleError> {
+        if !leaves.is_empty() && !leaves.len().is_power_of_two() {
+            return Err(MerkleError::NotPowerOfTwo { len: leaves.len() });
+        }
+        Self::try_new(leaves)
+    }
+
+    /// Builds a tree whose pairs are hashed in sorted order, see [`build_sorted`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_sorted(leaves: Vec<T>) -> Self {
+        let scheme = Scheme {
+            sorted_pairs: true,
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree that pairs odd nodes according to `odd`, see [`build_with`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_with(leaves: Vec<T>, odd: OddStrategy) -> Self {
+        let scheme = Scheme {
+            odd,
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a domain-separated tree, see [`build_tagged`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_tagged(leaves: Vec<T>, leaf_tag: u8, node_tag: u8) -> Self {
+        let scheme = Scheme {
+            tags: Some((leaf_tag, node_tag)),
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree whose leaf hashes are `hash(salt || leaf)`, so that leaves drawn from a
+    /// small set of values can't be recovered by hashing every candidate.
+    ///
+    /// The salt changes every leaf hash and so the root, and verifiers need it to check a
+    /// proof: [`verify_proof`] accepts one when given the salt followed by the leaf's
+    /// [`Hashable::hash_bytes`] as the leaf. The salt only helps while it stays private to the
+    /// tree's owner and those verifiers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_salted(leaves: Vec<T>, salt: [u8; 32]) -> Self {
+        let scheme = Scheme {
+            salt: Some(salt),
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree over `leaves` sorted by `order`, so that any permutation of the same leaves
+    /// gives the same root.
+    ///
+    /// Unlike [`GenericMerkleTree::new_sorted`], which sorts each pair of hashes, this sorts the
+    /// leaves themselves and then builds as [`GenericMerkleTree::new`] does. The root differs
+    /// from `new` over the unsorted input unless it was already in order. The sort is stable, so
+    /// equal leaves keep their relative order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_sorted_leaves(mut leaves: Vec<T>, order: LeafOrder) -> Self {
+        let scheme = Scheme::default();
+        match order {
+            LeafOrder::Bytes => leaves.sort_by_cached_key(Hashable::hash_bytes),
+            LeafOrder::Hash => leaves.sort_by_cached_key(|leaf| scheme.hash_leaf::<H, T, N>(leaf)),
+        }
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree over `leaves` with duplicates removed, keeping the first occurrence of each
+    /// and otherwise preserving their order.
+    ///
+    /// Leaves are compared by leaf hash, as in [`GenericMerkleTree::index_of`]. Removing
+    /// duplicates changes the leaf count, so the root differs from `new` over the same input
+    /// whenever it contained any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_deduped(leaves: Vec<T>) -> Self {
+        let scheme = Scheme::default();
+        let mut seen = BTreeSet::new();
+        let leaves = leaves
+            .into_iter()
+            .filter(|leaf| seen.insert(scheme.hash_leaf::<H, T, N>(leaf)))
+            .collect();
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree whose child hashes are joined according to `node_encoding`, see
+    /// [`build_with_node_encoding`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_with_node_encoding(leaves: Vec<T>, node_encoding: NodeEncoding) -> Self {
+        let scheme = Scheme {
+            node_encoding,
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaves, scheme).expect("cannot build a Merkle tree with no leaves")
+    }
+
+    /// Builds a tree whose leaves are encoded according to `encoding`, see
+    /// [`build_with_encoding`].
+    ///
+    /// Returns [`MerkleError::InvalidHex`] for the first leaf that isn't valid hex in
+    /// [`LeafEncoding::Hex`] mode, or [`MerkleError::EmptyTree`] if there are no leaves.
+    pub fn new_with_encoding(leaves: Vec<T>, encoding: LeafEncoding) -> Result<Self, MerkleError> {
+        if encoding == LeafEncoding::Hex {
+            if let Some(index) = leaves
+                .iter()
+                .position(|leaf| decode_hex(&leaf.hash_bytes()).is_none())
+            {
+                return Err(MerkleError::InvalidHex { index });
+            }
+        }
+        let scheme = Scheme {
+            encoding,
+            ..Scheme::default()
+        };
+        Self::with_scheme(leaves, scheme)
+    }
+
+    fn with_scheme(leaves: Vec<T>, scheme: Scheme) -> Result<Self, MerkleError> {
+        if leaves.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        check_leaf_count(leaves.len())?;
+
+        let hashes = build_levels::<H, T, N>(&leaves, scheme);
+        Ok(Self::from_parts(leaves, hashes, scheme))
+    }
+
+    // Assembles a tree from hashes already known to match `leaves`, caching its root
+    pub(crate) fn from_parts(leaves: Vec<T>, hashes: Levels<N>, scheme: Scheme) -> Self {
+        let mut tree = GenericMerkleTree {
+            leaves,
+            hashes,
+            root: [0u8; N],
+            scheme,
+            frozen: false,
+            pruned: false,
+            stale: false,
+            hasher: PhantomData,
+        };
+        tree.sync_root();
+        tree
+    }
+
+    // Refreshes the cached root after `hashes` changes
+    fn sync_root(&mut self) {
+        if let Some(root) = self.try_root_hash() {
+            self.root = root;
+        }
+    }
+
+    /// The salt the tree was built with by [`GenericMerkleTree::new_salted`], if any.
+    pub fn salt(&self) -> Option<[u8; 32]> {
+        self.scheme.salt
+    }
+
+    /// The order the tree joins each pair of child hashes in, so that a verifier given a
+    /// tree, or a deserialized one, knows how to fold its proofs.
+    pub fn pair_order(&self) -> PairOrder {
+        match self.scheme.sorted_pairs {
+            true => PairOrder::Sorted,
+            false => PairOrder::Positional,
+        }
+    }
+
+    /// The hash function the tree was built with, see [`MerkleHasher::ALGO`].
+    pub fn algo(&self) -> HashAlgo {
+        H::ALGO
+    }
+
+    /// The root hash, cached so that reading it is a single copy.
+    pub fn root_hash(&self) -> [u8; N] {
+        debug_assert_eq!(
+            Some(self.root),
+            self.try_root_hash(),
+            "cached root is stale"
+        );
+        self.root
+    }
+
+    /// The root hash, or `None` if the tree has no hashes.
+    ///
+    /// Constructors never produce such a tree, but code that can't rule it out (say, a tree of
+    /// unknown provenance) can use this instead of [`GenericMerkleTree::root_hash`], which has
+    /// no root to return.
+    pub fn try_root_hash(&self) -> Option<[u8; N]> {
+        self.get_node(0, 0)
+    }
+
+    /// The root hash as lowercase hex, without a `0x` prefix.
+    pub fn root_hex(&self) -> String {
+        hex::encode(self.root_hash())
+    }
+
+    /// The root hash as lowercase hex with a `0x` prefix, as shown by block explorers.
+    pub fn root_hex_prefixed(&self) -> String {
+        format!("0x{}", self.root_hex())
+    }
+
+    /// Whether the tree's root is `expected`.
+    pub fn has_root(&self, expected: &[u8; N]) -> bool {
+        self.root_hash() == *expected
+    }
+
+    /// Like [`GenericMerkleTree::has_root`], but takes the same time whichever bytes differ,
+    /// for code that gates a secret on the root matching one from an untrusted source.
+    ///
+    /// Every byte pair is XORed into one accumulator, which is only checked at the end, so the
+    /// comparison never stops early at the first mismatch.
+    pub fn ct_eq_root(&self, other: &[u8; N]) -> bool {
+        let root = self.root_hash();
+        let difference = root
+            .iter()
+            .zip(other)
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b));
+        // Keeps the compiler from turning the fold back into an early-exit comparison
+        core::hint::black_box(difference) == 0
+    }
+
+    /// Whether the tree's root is the hex string `expected`, in either case and with or
+    /// without a `0x` prefix. Returns `false` if `expected` isn't valid hex.
+    pub fn has_root_hex(&self, expected: &str) -> bool {
+        decode_hex(expected.as_bytes()).is_some_and(|bytes| bytes == self.root_hash())
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// The number of hashes across every level, leaves and root included, so 7 for 4 leaves.
+    ///
+    /// A pruned tree counts only what it still stores, its leaf hashes and root.
+    pub fn num_nodes(&self) -> usize {
+        self.levels().map(<[_]>::len).sum()
+    }
+
+    /// The hash at position `index` of `level`, or `None` if either is out of bounds.
+    ///
+    /// Levels count down from the root: level `0` is the root and level `depth() - 1` holds the
+    /// leaf hashes.
+    pub fn get_node(&self, level: usize, index: usize) -> Option<[u8; N]> {
+        self.level(level)?.get(index).copied()
+    }
+
+    /// The number of hashes at `level`, counting down from the root as in
+    /// [`GenericMerkleTree::get_node`], or `None` if the tree isn't that deep.
+    ///
+    /// The width of a level dropped by [`GenericMerkleTree::prune`] is still known, as it
+    /// follows from the leaf count.
+    pub fn width_at(&self, level: usize) -> Option<usize> {
+        let depth = self.depth();
+        (level < depth).then(|| {
+            let height = depth - 1 - level;
+            (0..height).fold(self.hashed_leaf_count(), |width, _| width.div_ceil(2))
+        })
+    }
+
+    /// The position of the parent of the node at `index` of `level`, as a `(level, index)` pair
+    /// in the root-first numbering of [`GenericMerkleTree::get_node`].
+    ///
+    /// The parent is always one level up at `index / 2`, including for a duplicated odd node,
+    /// which is the only child of its parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is the root or doesn't exist.
+    pub fn parent_index(&self, level: usize, index: usize) -> (usize, usize) {
+        self.check_node(level, index);
+        assert!(level > 0, "the root has no parent");
+        (level - 1, index / 2)
+    }
+
+    /// The indices, one level down, of the two children of the node at `index` of `level`.
+    ///
+    /// The right child is `None` when the left child is the last node of an odd-width level,
+    /// so the parent was hashed from it and its [`OddStrategy`] sibling rather than two stored
+    /// nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is a leaf or doesn't exist.
+    pub fn children_indices(&self, level: usize, index: usize) -> (usize, Option<usize>) {
+        self.check_node(level, index);
+        let children = self.width_at(level + 1).expect("a leaf has no children");
+        let right = 2 * index + 1;
+        (2 * index, (right < children).then_some(right))
+    }
+
+    fn check_node(&self, level: usize, index: usize) {
+        assert!(
+            self.get_node(level, index).is_some(),
+            "no node at index {} of level {}",
+            index,
+            level
+        );
+    }
+
+    /// Each level's hashes, from the root down to the leaf hashes.
+    ///
+    /// A pruned tree yields only its root and leaf hashes.
+    pub fn levels(&self) -> impl Iterator<Item = &[[u8; N]]> {
+        self.stored_levels().map(|(_, hashes)| hashes)
+    }
+
+    /// The stored hash of the leaf at `index`, exactly as the tree hashed it, or `None` if
+    /// `index` is out of bounds.
+    pub fn leaf_hash(&self, index: usize) -> Option<[u8; N]> {
+        self.hashes.last()?.get(index).copied()
+    }
+
+    /// The leaves the tree was built from, in order.
+    pub fn leaves(&self) -> &[T] {
+        &self.leaves
+    }
+
+    /// Each leaf with its index and stored hash, in leaf order, for example to export a
+    /// manifest of everything the root commits to. Nothing is rehashed.
+    pub fn entries(&self) -> impl Iterator<Item = (usize, &T, [u8; N])> {
+        let leaf_hashes = self.hashes.last().unwrap_or_default();
+        self.leaves
+            .iter()
+            .zip(leaf_hashes)
+            .enumerate()
+            .map(|(index, (leaf, hash))| (index, leaf, *hash))
+    }
+
+    /// Mutable access to the leaves, for editing many of them before a single
+    /// [`GenericMerkleTree::rebuild_in_place`].
+    ///
+    /// The tree's hashes and root are stale until then: lookups and the root still reflect the
+    /// old leaves, [`GenericMerkleTree::proof`] returns `None`,
+    /// [`GenericMerkleTree::try_proof`] returns [`MerkleError::Stale`], and the methods that
+    /// return proofs without a way to fail panic. Any method that changes the tree rebuilds it
+    /// first. Returns [`MerkleError::Frozen`] for a frozen tree.
+    pub fn leaves_mut(&mut self) -> Result<&mut Vec<T>, MerkleError> {
+        self.check_unfrozen()?;
+        self.stale = true;
+        Ok(&mut self.leaves)
+    }
+
+    /// Whether the leaves may have been edited through [`GenericMerkleTree::leaves_mut`] since
+    /// the tree was last rebuilt.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Whether any leaf hashes the same as `leaf`, see [`GenericMerkleTree::index_of`].
+    pub fn contains<L: Hashable + ?Sized>(&self, leaf: &L) -> bool {
+        self.index_of(leaf).is_some()
+    }
+
+    /// The lowest index whose leaf hash equals the hash of `leaf`.
+    ///
+    /// Matching is done on the leaf hash rather than the raw value, so any two values with the
+    /// same [`Hashable::hash_bytes`] match each other (e.g. `"ab"` and `b"ab".to_vec()`). In a
+    /// tree built from hashes, `leaf` is itself the leaf hash, see
+    /// [`GenericMerkleTree::from_hashes`].
+    pub fn index_of<L: Hashable + ?Sized>(&self, leaf: &L) -> Option<usize> {
+        let leaf_hash = self.scheme.lookup_hash::<H, L, N>(leaf)?;
+        self.hashes
+            .last()?
+            .iter()
+            .position(|hash| *hash == leaf_hash)
+    }
+
+    /// Whether any two leaves hash the same, in which case [`GenericMerkleTree::index_of`] only
+    /// ever finds the first of them.
+    pub fn has_duplicates(&self) -> bool {
+        let mut seen = BTreeSet::new();
+        self.hashes
+            .last()
+            .is_some_and(|leaf_hashes| !leaf_hashes.iter().all(|hash| seen.insert(hash)))
+    }
+
+    pub fn get_proof(&self, leaf_index: usize) -> Proof<N> {
+        self.assert_complete();
+        let mut siblings = vec![];
+        let mut index = leaf_index;
+
+        // Start at the leaves and work our way up to the root
+        for level in self.hashes.iter().skip(1).rev() {
+            siblings.push(self.scheme.sibling(level, index));
+            index /= 2;
+        }
+        Proof {
+            leaf_index,
+            leaf_count: self.leaves.len(),
+            siblings,
+        }
+    }
+
+    /// The number of siblings in [`GenericMerkleTree::proof`] for `leaf_index`, without building
+    /// it, or `None` if the index is out of bounds.
+    ///
+    /// Every proof has one sibling per level below the root, including levels where the
+    /// sibling is a duplicated odd node, so this is `depth() - 1` for any valid index.
+    pub fn expected_proof_len(&self, leaf_index: usize) -> Option<usize> {
+        (leaf_index < self.leaves.len()).then(|| self.proof_size())
+    }
+
+    /// The number of siblings in the longest proof the tree can produce, each `N` bytes.
+    pub fn proof_size(&self) -> usize {
+        self.depth() - 1
+    }
+
+    /// Returns the sibling hashes on the path from the leaf at `leaf_index` up to the root,
+    /// ordered bottom-to-top, or `None` if the index is out of bounds.
+    ///
+    /// A node that `build` duplicated to fill an odd level is its own sibling. A pruned or
+    /// stale tree has no siblings to return, so this is always `None`; see
+    /// [`GenericMerkleTree::try_proof`] to tell the cases apart.
+    pub fn proof(&self, leaf_index: usize) -> Option<Vec<[u8; N]>> {
+        if leaf_index >= self.leaves.len() || self.pruned || self.stale {
+            return None;
+        }
+
+        let mut proof = vec![];
+        let mut index = leaf_index;
+        for level in self.hashes.iter().skip(1).rev() {
+            proof.push(self.scheme.sibling(level, index));
+            index /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Like [`GenericMerkleTree::proof`], but returns [`MerkleError::Stale`] for a tree whose
+    /// leaves were edited, [`MerkleError::Pruned`] for a pruned tree and
+    /// [`MerkleError::IndexOutOfBounds`] for a bad index.
+    pub fn try_proof(&self, leaf_index: usize) -> Result<Vec<[u8; N]>, MerkleError> {
+        if self.stale {
+            return Err(MerkleError::Stale);
+        }
+        if self.pruned {
+            return Err(MerkleError::Pruned);
+        }
+        self.proof(leaf_index).ok_or(MerkleError::IndexOutOfBounds {
+            index: leaf_index,
+            len: self.leaves.len(),
+        })
+    }
+
+    /// Whether `proof` is exactly the sibling path this tree stores for `leaf_index`, compared
+    /// element by element with the sibling at each level.
+    ///
+    /// Unlike [`verify_proof`], which only checks that a proof folds up to the root, this
+    /// rejects a proof taken for a different leaf or from a different tree even where it
+    /// happens to fold to the same root, so it's suited to testing proof generators. Returns
+    /// `false` if `leaf_index` is out of bounds.
+    pub fn validate_proof_against_tree(&self, leaf_index: usize, proof: &[[u8; N]]) -> bool {
+        self.proof(leaf_index)
+            .is_some_and(|expected| expected == proof)
+    }
+
+    /// Every leaf paired with its [`GenericMerkleTree::proof`], in leaf order.
+    ///
+    /// This walks each level once, handing its siblings out to every path that passes through
+    /// it, rather than starting a separate walk from each leaf. With the `serde` feature the
+    /// result can be serialized directly, for example to give each recipient of an airdrop
+    /// their own entry.
+    pub fn leaves_and_proofs(&self) -> Vec<(&T, Vec<[u8; N]>)> {
+        self.assert_complete();
+        let mut proofs: Vec<Vec<[u8; N]>> = (0..self.leaves.len())
+            .map(|_| Vec::with_capacity(self.proof_size()))
+            .collect();
+        for (height, level) in self.hashes.iter().skip(1).rev().enumerate() {
+            for (leaf_index, proof) in proofs.iter_mut().enumerate() {
+                proof.push(self.scheme.sibling(level, leaf_index >> height));
+            }
+        }
+        self.leaves.iter().zip(proofs).collect()
+    }
+
+    /// The [`GenericMerkleTree::proof`] for the first leaf whose bytes are exactly `leaf`, or
+    /// `None` if there is no such leaf.
+    ///
+    /// The bytes are hashed literally, the same way `build` hashes a `Vec<u8>` leaf, so a leaf
+    /// that happens to look like hex is never decoded first.
+    pub fn proof_for_bytes(&self, leaf: &[u8]) -> Option<Vec<[u8; N]>> {
+        self.proof(self.index_of(leaf)?)
+    }
+
+    /// The index of `leaf` and its [`GenericMerkleTree::proof`], or `None` if it isn't in the
+    /// tree.
+    ///
+    /// Leaves are matched as in [`GenericMerkleTree::index_of`]; if the value appears more than
+    /// once, the proof is for its first occurrence.
+    pub fn proof_for_value<L: Hashable + ?Sized>(&self, leaf: &L) -> Option<(usize, Vec<[u8; N]>)> {
+        let index = self.index_of(leaf)?;
+        Some((index, self.proof(index)?))
+    }
+
+    pub fn prove(&self, value: T, proof: Proof<N>) -> bool {
+        fold_proof::<H, T, N>(self.scheme, &value, proof.to_pairs()) == self.root_hash()
+    }
+
+    /// Stops the tree from changing, typically once its root has been published.
+    ///
+    /// Every method that would change the tree's leaves or root returns
+    /// [`MerkleError::Frozen`] from then on, while reads and proofs work as before. There's no
+    /// way to unfreeze a tree, though a clone stays frozen and a rebuild from its leaves
+    /// doesn't. Merging, serializing and deserializing don't carry the flag over.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Whether [`GenericMerkleTree::freeze`] has been called.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Drops every level between the root and the leaf hashes, roughly halving the memory the
+    /// hashes take, for trees that are kept mostly to report their root.
+    ///
+    /// The root, the leaves and their hashes are kept, so roots, lookups and leaf hashes work
+    /// as before. Proofs need the dropped levels: [`GenericMerkleTree::proof`] returns `None`,
+    /// [`GenericMerkleTree::try_proof`] returns [`MerkleError::Pruned`], and the methods that
+    /// return proofs or diagrams without a way to fail panic until
+    /// [`GenericMerkleTree::rehydrate`] restores them. Changing the tree rehydrates it first.
+    pub fn prune(&mut self) {
+        self.hashes.prune();
+        self.pruned = self.hashes.len() < height_for(self.hashed_leaf_count());
+    }
+
+    /// Rehashes the levels dropped by [`GenericMerkleTree::prune`] from the stored leaf
+    /// hashes, leaving an unpruned tree as it is.
+    pub fn rehydrate(&mut self) {
+        if !self.pruned {
+            return;
+        }
+        let scheme = self.scheme;
+        let leaf_hashes = self.hashes.take_leaf_hashes();
+        self.hashes = Levels::build(leaf_hashes, |level, index| {
+            scheme.parent::<H, N>(level, index)
+        });
+        self.pruned = false;
+    }
+
+    // Every level, with any that `prune` dropped rehashed from the leaf hashes, so serializers
+    // write a pruned tree the same way as one that never was
+    pub(crate) fn full_levels(&self) -> Cow<'_, Levels<N>> {
+        if !self.pruned {
+            return Cow::Borrowed(&self.hashes);
+        }
+        let scheme = self.scheme;
+        let leaf_hashes = self.hashes.last().unwrap_or_default().to_vec();
+        Cow::Owned(Levels::build(leaf_hashes, |level, index| {
+            scheme.parent::<H, N>(level, index)
+        }))
+    }
+
+    /// Appends a leaf, rehashing only the right edge of the tree.
+    ///
+    /// Every other subtree is unchanged by an append, so this costs one hash per level rather
+    /// than a full rebuild.
+    pub fn push(&mut self, leaf: T) -> Result<(), MerkleError> {
+        self.check_mutable()?;
+        check_leaf_count(self.leaves.len().saturating_add(1))?;
+        let leaf_hash = self.scheme.hash_leaf::<H, T, N>(&leaf);
+        self.leaves.push(leaf);
+        let scheme = self.scheme;
+        self.hashes.append(&[leaf_hash], |level, index| {
+            scheme.parent::<H, N>(level, index)
+        });
+        self.sync_root();
+        Ok(())
+    }
+
+    /// Removes and returns the last leaf, rehashing only the right edge of the tree, so the
+    /// result is the same as a rebuild over the remaining leaves.
+    ///
+    /// A tree can't be empty, so this returns `Ok(None)` rather than removing the only leaf.
+    pub fn pop(&mut self) -> Result<Option<T>, MerkleError> {
+        self.check_mutable()?;
+        if self.leaves.len() < 2 {
+            return Ok(None);
+        }
+        let leaf = self.leaves.pop();
+        let scheme = self.scheme;
+        self.hashes.truncate(self.leaves.len(), |level, index| {
+            scheme.parent::<H, N>(level, index)
+        });
+        self.sync_root();
+        Ok(leaf)
+    }
+
+    /// Appends a batch of leaves, rehashing each affected node once.
+    ///
+    /// The result is the same as a rebuild over all the leaves, but only the nodes to the right
+    /// of the old last leaf are recomputed, which is much cheaper than one
+    /// [`GenericMerkleTree::push`] per leaf.
+    pub fn append_many(&mut self, leaves: Vec<T>) -> Result<(), MerkleError> {
+        self.check_mutable()?;
+        check_leaf_count(self.leaves.len().saturating_add(leaves.len()))?;
+        let scheme = self.scheme;
+        let leaf_hashes: Vec<[u8; N]> = leaves
+            .iter()
+            .map(|leaf| scheme.hash_leaf::<H, T, N>(leaf))
+            .collect();
+        self.leaves.extend(leaves);
+        self.hashes.append(&leaf_hashes, |level, index| {
+            scheme.parent::<H, N>(level, index)
+        });
+        self.sync_root();
+        Ok(())
+    }
+
+    /// Combines two trees into one whose root is `hash(left root || right root)`, reusing their
+    /// stored hashes rather than rehashing any leaves.
+    ///
+    /// The result is the same tree as building over the leaves of `left` followed by those of
+    /// `right`, so its proofs verify as usual. That only works when `left` is a complete tree,
+    /// with a power-of-two number of leaves, so that `right`'s leaves start a new subtree.
+    ///
+    /// If `right` is shallower than `left`, it's padded up to the same depth the way any odd
+    /// node is: its root is hashed with its [`OddStrategy`] sibling once per missing level, and
+    /// the padded root is what's combined with `left`'s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `left`'s leaf count isn't a power of two, if `right` is deeper than `left`, or
+    /// if the trees were built with different schemes. Pruned trees are rehydrated and stale
+    /// ones rebuilt first, which panics if every leaf of one was removed.
+    pub fn merge(mut left: Self, mut right: Self) -> Self {
+        for tree in [&mut left, &mut right] {
+            tree.refresh()
+                .expect("cannot merge a tree whose leaves were all removed");
+        }
+        assert!(
+            left.leaves.len().is_power_of_two(),
+            "cannot merge onto a tree with {} leaves, which isn't a power of two",
+            left.leaves.len()
+        );
+        assert!(
+            right.hashes.len() <= left.hashes.len(),
+            "cannot merge a deeper tree onto a shallower one"
+        );
+        assert!(
+            left.scheme == right.scheme,
+            "cannot merge trees built with different schemes"
+        );
+
+        let scheme = left.scheme;
+        let mut right_levels = right.hashes.to_nested();
+        while right_levels.len() < left.hashes.len() {
+            let root = right_levels[0][0];
+            let padded = scheme.hash_nodes::<H, N>(&root, &scheme.odd_sibling(&root));
+            right_levels.insert(0, vec![padded]);
+        }
+
+        let root = scheme.hash_nodes::<H, N>(&left.hashes[0][0], &right_levels[0][0]);
+        let mut levels = vec![vec![root]];
+        for (left_level, right_level) in left.hashes.iter().zip(right_levels) {
+            levels.push([left_level, &right_level].concat());
+        }
+
+        let mut leaves = left.leaves;
+        leaves.extend(right.leaves);
+        Self::from_parts(leaves, Levels::from_nested(levels), scheme)
+    }
+
+    /// A new tree built the same way as this one over `f` applied to each leaf, for example
+    /// to normalize leaves before committing to them.
+    ///
+    /// The result is fully rebuilt, so it isn't frozen even if this tree is.
+    pub fn map_leaves<U: Hashable>(&self, f: impl FnMut(&T) -> U) -> GenericMerkleTree<U, H, N> {
+        let leaves = self.leaves.iter().map(f).collect();
+        GenericMerkleTree::with_scheme(leaves, self.scheme)
+            .expect("a tree always has at least one leaf")
+    }
+
+    /// Splits the tree into one over `leaves[..index]` and one over `leaves[index..]`, each
+    /// built independently the same way as this one, for example to shard proof serving.
+    ///
+    /// The stored leaf hashes are reused, so only the levels above them are hashed again. A
+    /// tree can't be empty, so an `index` of `0` or `leaf_count()` returns
+    /// [`MerkleError::EmptyTree`], and one past that [`MerkleError::IndexOutOfBounds`]. A
+    /// stale tree returns [`MerkleError::Stale`], as its leaf hashes may not match its leaves.
+    pub fn split_at(&self, index: usize) -> Result<(Self, Self), MerkleError>
+    where
+        T: Clone,
+    {
+        if self.stale {
+            return Err(MerkleError::Stale);
+        }
+        let len = self.leaves.len();
+        if index > len {
+            return Err(MerkleError::IndexOutOfBounds { index, len });
+        }
+        if index == 0 || index == len {
+            return Err(MerkleError::EmptyTree);
+        }
+
+        let scheme = self.scheme;
+        let leaf_hashes = &self.hashes[self.hashes.len() - 1];
+        let part = |leaves: &[T], leaf_hashes: &[[u8; N]]| {
+            let levels = Levels::build(leaf_hashes.to_vec(), |level, index| {
+                scheme.parent::<H, N>(level, index)
+            });
+            Self::from_parts(leaves.to_vec(), levels, scheme)
+        };
+        Ok((
+            part(&self.leaves[..index], &leaf_hashes[..index]),
+            part(&self.leaves[index..], &leaf_hashes[index..]),
+        ))
+    }
+
+    /// Recomputes the tree from its leaves and checks that every stored hash matches,
+    /// including the root of a pruned tree.
+    pub fn verify_self(&self) -> bool {
+        let rebuilt = build_levels::<H, T, N>(&self.leaves, self.scheme);
+        rebuilt.len() == self.depth()
+            && self
+                .stored_levels()
+                .all(|(level, hashes)| rebuilt[level] == *hashes)
+    }
+
+    /// The indices of the leaves whose hashes differ between `self` and `other`, in order.
+    ///
+    /// Both trees are walked from the root down, only descending into nodes whose hashes
+    /// differ, so subtrees the trees share are skipped whole and the cost is proportional to
+    /// the number of changed leaves times the depth. Returns
+    /// [`MerkleError::LeafCountMismatch`] unless the trees have the same number of leaves.
+    /// Trees built with different schemes differ everywhere, so every leaf is reported. The
+    /// walk needs every level, so either tree being pruned returns [`MerkleError::Pruned`],
+    /// and either being stale [`MerkleError::Stale`].
+    pub fn diff(&self, other: &Self) -> Result<Vec<usize>, MerkleError> {
+        if self.stale || other.stale {
+            return Err(MerkleError::Stale);
+        }
+        if self.pruned || other.pruned {
+            return Err(MerkleError::Pruned);
+        }
+        if self.leaves.len() != other.leaves.len() {
+            return Err(MerkleError::LeafCountMismatch {
+                left: self.leaves.len(),
+                right: other.leaves.len(),
+            });
+        }
+
+        // Start from a parent above the root, whose only child is the root itself
+        let mut differing = vec![0];
+        for (ours, theirs) in self.hashes.iter().zip(other.hashes.iter()) {
+            differing = differing
+                .iter()
+                .flat_map(|index| [2 * index, 2 * index + 1])
+                .filter(|index| *index < ours.len() && ours[*index] != theirs[*index])
+                .collect();
+        }
+        Ok(differing)
+    }
+
+    /// Rehashes the whole tree from its current leaves, after editing them through
+    /// [`GenericMerkleTree::leaves_mut`], reusing the buffer that held the old hashes.
+    ///
+    /// Returns [`MerkleError::Frozen`] for a frozen tree, or [`MerkleError::EmptyTree`] if
+    /// every leaf was removed. An emptied tree keeps its old hashes but stays stale, so it
+    /// gives no proofs and every change returns the same error, until leaves are added back
+    /// through [`GenericMerkleTree::leaves_mut`] and it's rebuilt.
+    pub fn rebuild_in_place(&mut self) -> Result<(), MerkleError> {
+        self.check_unfrozen()?;
+        if self.leaves.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        let buffer = self.hashes.take_buffer();
+        self.hashes = build_levels_into::<H, T, N>(buffer, &self.leaves, self.scheme);
+        self.pruned = false;
+        self.stale = false;
+        self.sync_root();
+        Ok(())
+    }
+
+    /// Replaces the leaf at `index` and rehashes the single path from it up to the root.
+    pub fn update_leaf(&mut self, index: usize, new_leaf: T) -> Result<(), MerkleError> {
+        self.check_mutable()?;
+        self.check_update(index, &new_leaf)?;
+
+        let leaf_level = self.hashes.len() - 1;
+        self.hashes[leaf_level][index] = self.scheme.hash_leaf::<H, T, N>(&new_leaf);
+        self.leaves[index] = new_leaf;
+        self.rehash_path(index);
+        Ok(())
+    }
+
+    /// Replaces several leaves at once, recomputing each affected ancestor only once even when
+    /// the changed leaves share it.
+    ///
+    /// Every change is checked before any is applied, so an error leaves the tree untouched. If
+    /// an index appears more than once, its last change wins.
+    pub fn update_many(&mut self, changes: &[(usize, T)]) -> Result<(), MerkleError>
+    where
+        T: Clone,
+    {
+        self.check_mutable()?;
+        for (index, leaf) in changes {
+            self.check_update(*index, leaf)?;
+        }
+
+        let leaf_level = self.hashes.len() - 1;
+        let mut changed: Vec<usize> = changes.iter().map(|(index, _)| *index).collect();
+        for (index, leaf) in changes {
+            self.hashes[leaf_level][*index] = self.scheme.hash_leaf::<H, T, N>(leaf);
+            self.leaves[*index] = leaf.clone();
+        }
+
+        for level in (1..self.hashes.len()).rev() {
+            // Siblings share a parent, so each parent is recomputed once
+            changed.iter_mut().for_each(|index| *index /= 2);
+            changed.sort_unstable();
+            changed.dedup();
+            for &parent in &changed {
+                self.hashes[level - 1][parent] =
+                    self.scheme.parent::<H, N>(&self.hashes[level], parent * 2);
+            }
+        }
+        self.sync_root();
+        Ok(())
+    }
+
+    fn check_unfrozen(&self) -> Result<(), MerkleError> {
+        match self.frozen {
+            true => Err(MerkleError::Frozen),
+            false => Ok(()),
+        }
+    }
+
+    // Checks that the tree can change, first rebuilding it if it's stale or rehydrating it if
+    // it was pruned
+    fn check_mutable(&mut self) -> Result<(), MerkleError> {
+        self.check_unfrozen()?;
+        self.refresh()
+    }
+
+    // Brings the hashes back in line with the leaves, failing only if there are none
+    fn refresh(&mut self) -> Result<(), MerkleError> {
+        if self.stale {
+            return self.rebuild_in_place();
+        }
+        self.rehydrate();
+        Ok(())
+    }
+
+    // Checks that `leaf` can replace the leaf at `index`
+    fn check_update(&self, index: usize, leaf: &T) -> Result<(), MerkleError> {
+        self.check_unfrozen()?;
+        let len = self.leaves.len();
+        if index >= len {
+            return Err(MerkleError::IndexOutOfBounds { index, len });
+        }
+        if self.scheme.encoding == LeafEncoding::Hex && decode_hex(&leaf.hash_bytes()).is_none() {
+            return Err(MerkleError::InvalidHex { index });
+        }
+        Ok(())
+    }
+
+    // Recomputes every ancestor of the leaf at `leaf_index` from its children
+    fn rehash_path(&mut self, leaf_index: usize) {
+        let mut index = leaf_index;
+        for level in (1..self.hashes.len()).rev() {
+            let parent = self
+                .scheme
+                .parent::<H, N>(&self.hashes[level], index - index % 2);
+            index /= 2;
+            self.hashes[level - 1][index] = parent;
+        }
+        self.sync_root();
+    }
+}
+
+impl<L, M, H, const N: usize> GenericMerkleTree<(L, M), H, N> {
+    /// The metadata stored alongside the leaf at `index`, or `None` if `index` is out of
+    /// bounds.
+    pub fn leaf_meta(&self, index: usize) -> Option<&M> {
+        self.leaves.get(index).map(|(_, meta)| meta)
+    }
+}
+
+impl<T: Hashable> MerkleTree<T> {
+    /// The last 20 bytes of the root, the way Ethereum derives an address from a Keccak-256
+    /// hash, for storing the commitment on chain in a single `address`-sized slot.
+    ///
+    /// This is lossy: it's only as collision-resistant as a 160-bit hash, so it must only be
+    /// used where a 20-byte commitment is acceptable. It isn't the root of a
+    /// [`TruncatedKeccak256`] tree either, which keeps the first 20 bytes of every node.
+    pub fn root_address(&self) -> [u8; 20] {
+        let root = self.root_hash();
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&root[12..]);
+        address
+    }
+}
+
+impl<const N: usize> Proof<N> {
+    /// Converts the proof into the `(is_left, sibling)` pairs accepted by [`verify_proof`].
+    pub fn to_pairs(&self) -> Vec<(bool, [u8; N])> {
+        self.siblings
+            .iter()
+            .enumerate()
+            .map(|(height, hash)| (is_left_sibling(self.leaf_index, height), *hash))
+            .collect()
+    }
+
+    /// Packs the proof's directions into a [`CompactProof`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proof has more than 64 siblings, which no tree that fits in memory needs.
+    pub fn to_compact(&self) -> CompactProof<N> {
+        assert!(self.siblings.len() <= 64, "proof is too long to compact");
+        let directions = (0..self.siblings.len())
+            .filter(|height| is_left_sibling(self.leaf_index, *height))
+            .fold(0, |directions, height| directions | 1 << height);
+        CompactProof {
+            directions,
+            siblings: self.siblings.clone(),
+        }
+    }
+
+    /// Unpacks a [`CompactProof`] taken from a tree of `leaf_count` leaves, or returns `None`
+    /// if it has more than 64 siblings, sets direction bits past its last sibling, or points
+    /// past the last leaf.
+    ///
+    /// The directions of a canonical compact proof are exactly its leaf's index.
+    pub fn from_compact(compact: &CompactProof<N>, leaf_count: usize) -> Option<Self> {
+        let leaf_index = usize::try_from(compact.directions).ok()?;
+        (compact.is_canonical() && leaf_index < leaf_count).then(|| Proof {
+            leaf_index,
+            leaf_count,
+            siblings: compact.siblings.clone(),
+        })
+    }
+}
+
+impl Proof {
+    /// Checks that `leaf` is the leaf at `leaf_index` in a Keccak-256 tree of `leaf_count`
+    /// leaves with root `root`, as built by [`MerkleTree::new`].
+    ///
+    /// Returns `false` rather than panicking if the index is out of bounds, or if the number
+    /// of siblings isn't the depth of a tree that size, before hashing anything.
+    pub fn verify<L: Hashable + ?Sized>(&self, leaf: &L, root: [u8; 32]) -> bool {
+        self.leaf_index < self.leaf_count
+            && self.siblings.len() == height_for(self.leaf_count) - 1
+            && fold_proof::<Keccak256, L, 32>(Scheme::default(), leaf, self.to_pairs()) == root
+    }
+}
+
+// Whether the sibling `height` levels above the leaves is the left child on the path from
+// `leaf_index`, which is when the path's node at that height is a right child
+fn is_left_sibling(leaf_index: usize, height: usize) -> bool {
+    height < usize::BITS as usize && leaf_index >> height & 1 == 1
+}
+
+/// A [`Proof`] with every direction packed into one integer, for sending over the wire.
+///
+/// It carries the same information as [`Proof::to_pairs`] in one bit per level rather than a
+/// `bool` each, and is checked with [`verify_compact`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactProof<const N: usize = 32> {
+    /// Bit `i` is set when `siblings[i]` is the left child.
+    pub directions: u64,
+    /// The sibling hashes, ordered from the leaf level upward.
+    pub siblings: Vec<[u8; N]>,
+}
+
+impl<const N: usize> CompactProof<N> {
+    // Whether exactly one `Proof` packs into this
+    fn is_canonical(&self) -> bool {
+        let len = self.siblings.len() as u32;
+        len <= u64::BITS && self.directions.checked_shr(len).unwrap_or(0) == 0
+    }
+
+    fn pairs(&self) -> impl Iterator<Item = (bool, [u8; N])> + '_ {
+        let directions = self.directions;
+        (self.siblings.iter().enumerate()).map(move |(i, hash)| (directions >> i & 1 == 1, *hash))
+    }
+}
+
+/// Like [`verify_proof`], but for a [`CompactProof`].
+///
+/// Accepts exactly the compact forms of the proofs [`verify_proof`] accepts: any direction bit
+/// set past the last sibling makes the proof invalid.
+pub fn verify_compact<L: Hashable + ?Sized>(
+    leaf: &L,
+    proof: &CompactProof,
+    root: [u8; 32],
+    algo: HashAlgo,
+) -> bool {
+    if !proof.is_canonical() {
+        return false;
+    }
+    let pairs: Vec<(bool, [u8; 32])> = proof.pairs().collect();
+    verify_proof(leaf, &pairs, root, algo)
+}
+
+/// Checks that `leaf` is included under `root`, a root built with `algo`, without needing the
+/// tree itself.
+///
+/// Each proof element is a `(is_left, sibling)` pair, ordered from the leaf level upward.
+/// `is_left` is `true` when the sibling is the left child, in which case the parent is
+/// `hash(sibling || node)`; otherwise the parent is `hash(node || sibling)`. This matches
+/// the `chunk[0] || chunk[1]` concatenation used by [`build`].
+///
+/// Always returns `false` for [`HashAlgo::Custom`], and for algorithms whose feature isn't
+/// enabled, since there's no hasher to recompute the root with. Likewise for
+/// [`HashAlgo::Keccak512`], whose 64-byte nodes don't fit these 32-byte proofs.
+///
+/// Never panics, whatever the proof contains. A proof longer than [`MAX_PROOF_LEN`] is rejected
+/// without hashing it, as no tree that fits in memory is that deep. An empty proof is only
+/// valid for a single-leaf tree, whose root is the leaf hash.
+pub fn verify_proof<L: Hashable + ?Sized>(
+    leaf: &L,
+    proof: &[(bool, [u8; 32])],
+    root: [u8; 32],
+    algo: HashAlgo,
+) -> bool {
+    if proof.len() > MAX_PROOF_LEN {
+        return false;
+    }
+    let scheme = Scheme::default();
+    let computed = match algo {
+        HashAlgo::Keccak256 => fold_proof::<Keccak256, L, 32>(scheme, leaf, proof.iter().copied()),
+        #[cfg(feature = "sha2")]
+        HashAlgo::Sha256 => fold_proof::<Sha256, L, 32>(scheme, leaf, proof.iter().copied()),
+        #[cfg(feature = "blake3")]
+        HashAlgo::Blake3 => fold_proof::<Blake3, L, 32>(scheme, leaf, proof.iter().copied()),
+        #[allow(unreachable_patterns)]
+        _ => return false,
+    };
+    computed == root
+}
+
+/// Like [`verify_proof`] for [`HashAlgo::Keccak256`], but folds each `(is_left, sibling)` pair
+/// as `siblings` yields it, so a proof arriving piece by piece never has to be buffered.
+///
+/// A proof may have at most [`MAX_PROOF_LEN`] pairs: if `siblings` yields any more, the proof
+/// is rejected as soon as the next one arrives, so an endless iterator can't stall the
+/// verifier.
+pub fn verify_proof_streaming<L: Hashable + ?Sized>(
+    leaf: &L,
+    siblings: impl Iterator<Item = (bool, [u8; 32])>,
+    root: [u8; 32],
+) -> bool {
+    let mut len = 0;
+    let siblings = siblings.take(MAX_PROOF_LEN + 1).inspect(|_| len += 1);
+    let computed = fold_proof::<Keccak256, L, 32>(Scheme::default(), leaf, siblings);
+    len <= MAX_PROOF_LEN && computed == root
+}
+
+/// Checks a proof from a sorted-pair tree, such as [`MerkleTree::new_sorted`] builds, against
+/// its Keccak-256 `root`.
+///
+/// Each step hashes the running node and the next sibling as `min || max`, exactly as
+/// OpenZeppelin's `MerkleProof.processProof` does, so the proof is just the sibling hashes
+/// from the leaf level upward, with no directions. As in [`verify_proof`], a proof longer than
+/// [`MAX_PROOF_LEN`] is rejected without hashing it.
+pub fn verify_proof_sorted<L: Hashable + ?Sized>(
+    leaf: &L,
+    proof: &[[u8; 32]],
+    root: [u8; 32],
+) -> bool {
+    if proof.len() > MAX_PROOF_LEN {
+        return false;
+    }
+    let scheme = Scheme {
+        sorted_pairs: true,
+        ..Scheme::default()
+    };
+    // The pair is reordered by value, so the direction passed here never matters
+    let siblings = proof.iter().map(|sibling| (false, *sibling));
+    fold_proof::<Keccak256, L, 32>(scheme, leaf, siblings) == root
+}
+
+/// The Keccak-256 root that `proof` folds `leaf` up to, for diagnosing a proof that
+/// [`verify_proof`] rejects.
+///
+/// The proof is folded exactly as in [`verify_proof`], which for [`HashAlgo::Keccak256`]
+/// accepts precisely when this equals the expected root and the proof is no longer than
+/// [`MAX_PROOF_LEN`]. Comparing the result with the root of a freshly built tree, or folding a
+/// known-good leaf the same way, narrows a mismatch down to the leaf, the proof or the root.
+pub fn compute_root_from_proof<L: Hashable + ?Sized>(
+    leaf: &L,
+    proof: &[(bool, [u8; 32])],
+) -> [u8; 32] {
+    fold_proof::<Keccak256, L, 32>(Scheme::default(), leaf, proof.iter().copied())
+}
+
+/// Hashes leaf data exactly as [`build`] and [`MerkleTree::new`] do, for code that builds or
+/// checks proofs without this crate's tree types.
+///
+/// This is a plain Keccak-256 of the bytes (the original Keccak padding, as in Ethereum, not
+/// NIST SHA3-256), so a string leaf's hash is `hash_leaf(leaf.as_bytes())`.
+pub fn hash_leaf(data: &[u8]) -> [u8; 32] {
+    Scheme::default().hash_leaf::<Keccak256, [u8], 32>(data)
+}
+
+/// Hashes two children into their parent exactly as [`build`] and [`MerkleTree::new`] do:
+/// Keccak-256 of `left || right`.
+pub fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Scheme::default().hash_nodes::<Keccak256, 32>(left, right)
+}
+
+fn fold_proof<H: MerkleHasher<N>, L: Hashable + ?Sized, const N: usize>(
+    scheme: Scheme,
+    leaf: &L,
+    proof: impl IntoIterator<Item = (bool, [u8; N])>,
+) -> [u8; N] {
+    let mut hash = scheme.hash_leaf::<H, L, N>(leaf);
+
+    for (is_left, sibling_hash) in proof {
+        hash = if is_left {
+            scheme.hash_nodes::<H, N>(&sibling_hash, &hash)
+        } else {
+            scheme.hash_nodes::<H, N>(&hash, &sibling_hash)
+        };
+    }
+
+    hash
+}
+
+/// The number of levels [`build`] produces for `leaf_count` leaves, counting both the root and
+/// the leaves, without building anything.
+///
+/// Each level above the leaves is half the width of the one below, rounded up, so this is
+/// `ceil(log2(leaf_count)) + 1`: 5 leaves give 4 levels. No leaves give no levels at all.
+pub fn height_for(leaf_count: usize) -> usize {
+    match leaf_count {
+        0 => 0,
+        count => (usize::BITS - (count - 1).leading_zeros()) as usize + 1,
+    }
+}
+
+/// Hashes `leaves` into every level of a Keccak-256 tree, root first.
+///
+/// One leaf gives a single level holding its hash, which is also the root, as in Bitcoin and
+/// OpenZeppelin's trees.
+///
+/// Takes the leaves by reference so callers can keep using them; code written against the old
+/// owning signature can call [`build_owned`] until it's updated.
+pub fn build<T: Hashable>(leaves: &[T]) -> Vec<Vec<[u8; 32]>> {
+    build_levels::<Keccak256, T, 32>(leaves, Scheme::default()).to_nested()
+}
+
+/// The previous, owning signature of [`build`].
+#[deprecated(note = "use `build(&leaves)`, which borrows the leaves instead")]
+pub fn build_owned<T: Hashable>(leaves: Vec<T>) -> Vec<Vec<[u8; 32]>> {
+    build(&leaves)
+}
+
+/// Like [`build`], but hashes leaves and nodes with `H` instead of Keccak-256.
+///
+/// Returns no levels at all for an empty `leaves`.
+pub fn build_with_hasher<H: MerkleHasher<N>, T: Hashable, const N: usize>(
+    leaves: Vec<T>,
+) -> Vec<Vec<[u8; N]>> {
+    build_levels::<H, T, N>(&leaves, Scheme::default()).to_nested()
+}
+
+/// Like [`build`], but each pair is concatenated smallest-hash-first (`min || max`) before
+/// hashing. This matches OpenZeppelin's `MerkleProof`, so proofs need no direction flags.
+pub fn build_sorted<T: Hashable>(leaves: Vec<T>) -> Vec<Vec<[u8; 32]>> {
+    let scheme = Scheme {
+        sorted_pairs: true,
+        ..Scheme::default()
+    };
+    build_levels::<Keccak256, T, 32>(&leaves, scheme).to_nested()
+}
+
+/// Like [`build`], but pairs the last node of each odd level according to `odd`.
+///
+/// [`OddStrategy::PadZero`] produces different roots from the default duplication rule.
+pub fn build_with<T: Hashable>(leaves: Vec<T>, odd: OddStrategy) -> Vec<Vec<[u8; 32]>> {
+    let scheme = Scheme {
+        odd,
+        ..Scheme::default()
+    };
+    build_levels::<Keccak256, T, 32>(&leaves, scheme).to_nested()
+}
+
+/// Like [`build`], but prefixes every leaf with `leaf_tag` and every node concatenation with
+/// `node_tag` before hashing, so a leaf can never be passed off as an internal node.
+///
+/// Conventionally `leaf_tag` is `0x00` and `node_tag` is `0x01`. The tags change every hash, so
+/// tagged roots are not compatible with untagged ones.
+pub fn build_tagged<T: Hashable>(leaves: Vec<T>, leaf_tag: u8, node_tag: u8) -> Vec<Vec<[u8; 32]>> {
+    let scheme = Scheme {
+        tags: Some((leaf_tag, node_tag)),
+        ..Scheme::default()
+    };
+    build_levels::<Keccak256, T, 32>(&leaves, scheme).to_nested()
+}
+
+/// Like [`build`], but encodes each leaf according to `encoding` before hashing.
+///
+/// With [`LeafEncoding::Hex`], a leaf such as `"0xdeadbeef"` is hashed as the four bytes
+/// `de ad be ef` rather than as its text, and the first leaf that isn't valid hex is reported
+/// as [`MerkleError::InvalidHex`].
+pub fn build_with_encoding<T: Hashable>(
+    leaves: Vec<T>,
+    encoding: LeafEncoding,
+) -> Result<Vec<Vec<[u8; 32]>>, MerkleError> {
+    if leaves.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(MerkleTree::new_with_encoding(leaves, encoding)?
+        .hashes
+        .to_nested())
+}
+
+/// Like [`build`], but joins each pair of child hashes according to `node_encoding` instead of
+/// concatenating them directly. Any encoding other than [`NodeEncoding::Raw`] changes the root.
+pub fn build_with_node_encoding<T: Hashable>(
+    leaves: Vec<T>,
+    node_encoding: NodeEncoding,
+) -> Vec<Vec<[u8; 32]>> {
+    let scheme = Scheme {
+        node_encoding,
+        ..Scheme::default()
+    };
+    build_levels::<Keccak256, T, 32>(&leaves, scheme).to_nested()
+}
+
+/// Like [`build`], but takes already-hashed leaves, which become the leaf level unchanged.
+pub fn build_from_hashes(leaf_hashes: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let scheme = Scheme {
+        prehashed: true,
+        ..Scheme::default()
+    };
+    build_levels::<Keccak256, [u8; 32], 32>(&leaf_hashes, scheme).to_nested()
+}
+
+/// Computes the same root as `MerkleTree::new(leaves).root_hash()` without keeping the tree.
+///
+/// Each level is hashed in place over the leaf level's buffer, so only one level is held in
+/// memory at a time. Prefer this over building a tree when the root is all you need.
+///
+/// # Panics
+///
+/// Panics if `leaves` is empty.
+pub fn compute_root<T: Hashable>(leaves: &[T]) -> [u8; 32] {
+    assert!(!leaves.is_empty(), "{}", MerkleError::EmptyTree);
+    let scheme = Scheme::default();
+    let mut level: Vec<[u8; 32]> = leaves
+        .iter()
+        .map(|leaf| scheme.hash_leaf::<Keccak256, T, 32>(leaf))
+        .collect();
+
+    while level.len() > 1 {
+        let width = level.len().div_ceil(2);
+        for i in 0..width {
+            let left = level[2 * i];
+            let right = match level.get(2 * i + 1) {
+                Some(right) => *right,
+                None => scheme.odd_sibling(&left),
+            };
+            // Parent `i` only overwrites nodes that have already been read
+            level[i] = scheme.hash_nodes::<Keccak256, 32>(&left, &right);
+        }
+        level.truncate(width);
+    }
+
+    level[0]
+}
+
+fn build_levels<H: MerkleHasher<N>, T: Hashable, const N: usize>(
+    leaves: &[T],
+    scheme: Scheme,
+) -> Levels<N> {
+    build_levels_into::<H, T, N>(Vec::new(), leaves, scheme)
+}
+
+// Like `build_levels`, but collects the leaf hashes into `buffer`, which must be empty, so its
+// allocation is reused
+fn build_levels_into<H: MerkleHasher<N>, T: Hashable, const N: usize>(
+    buffer: Vec<[u8; N]>,
+    leaves: &[T],
+    scheme: Scheme,
+) -> Levels<N> {
+    let mut leaf_hashes = buffer;
+    leaf_hashes.reserve_exact(Levels::<N>::node_count(leaves.len()).unwrap_or(leaves.len()));
+    leaf_hashes.extend(leaves.iter().map(|leaf| scheme.hash_leaf::<H, T, N>(leaf)));
+
+    // Pair up leaf hashes and hash them together to make the next level of the tree. With a
+    // power-of-two leaf count no level has an odd node, so the sibling is always `index + 1`
+    if leaf_hashes.len().is_power_of_two() {
+        Levels::build(leaf_hashes, |level, index| {
+            scheme.hash_nodes::<H, N>(&level[index], &level[index + 1])
+        })
+    } else {
+        Levels::build(leaf_hashes, |level, index| {
+            scheme.parent::<H, N>(level, index)
+        })
+    }
+}
+
+/// A compact, per-field view with every hash as hex. Use `Display` for a level-by-level
+/// diagram instead.
+impl<T: core::fmt::Debug, H, const N: usize> core::fmt::Debug for GenericMerkleTree<T, H, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("GenericMerkleTree")
+            .field("root", &HexNode(&self.root))
+            .field("leaves", &self.leaves)
+            .field("hashes", &self.hashes)
+            .field("scheme", &self.scheme)
+            .field("frozen", &self.frozen)
+            .field("pruned", &self.pruned)
+            .field("stale", &self.stale)
+            .finish()
+    }
+}
+
+impl<T: Hashable, H, const N: usize> core::fmt::Display for GenericMerkleTree<T, H, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut tree_str = String::new();
+
+        // Print the leaves
+        tree_str.push_str("Leaves:\n");
+        for (i, leaf) in self.leaves.iter().enumerate() {
+            tree_str.push_str(&format!("  {}: {}\n", i, leaf.display_leaf()));
+        }
+
+        // Print the hashes
+        for (level, hashes) in self.stored_levels().rev() {
+            if hashes.len() == 1 {
+                tree_str.push_str("Root Hash:\n");
+                tree_str.push_str(&format!("  {}\n", hex::encode(hashes[0])));
+            } else {
+                tree_str.push_str(&format!("Level {}:\n", level));
+                for hash in hashes {
+                    tree_str.push_str(&format!("  {}\n", hex::encode(hash)));
+                }
+            }
+        }
+
+        write!(f, "{}", tree_str)
+    }
+}
+
+impl<T: Hashable, H, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Like the tree's `Display` output, but sized for trees too large to print in full.
+    ///
+    /// Only the first and last few entries of the leaves and of each level are shown, at most
+    /// `max_level_width` of each, and likewise only the top and bottom levels, at most
+    /// `max_level_width` of them. Whatever is left out is replaced by a line counting it.
+    /// Trees that fit within the limit print exactly as with `Display`.
+    pub fn display_summary(&self, max_level_width: usize) -> String {
+        let max = max_level_width.max(1);
+        let mut summary = String::from("Leaves:\n");
+        push_elided(&mut summary, self.leaves.len(), max, "leaves", |i| {
+            format!("  {}: {}\n", i, self.leaves[i].display_leaf())
+        });
+
+        let levels: Vec<(usize, &[[u8; N]])> = self.stored_levels().rev().collect();
+        push_elided(&mut summary, levels.len(), max, "levels", |i| {
+            let (level, hashes) = levels[i];
+            if hashes.len() == 1 {
+                return format!("Root Hash:\n  {}\n", hex::encode(hashes[0]));
+            }
+            let mut lines = format!("Level {}:\n", level);
+            push_elided(&mut lines, hashes.len(), max, "hashes", |i| {
+                format!("  {}\n", hex::encode(hashes[i]))
+            });
+            lines
+        });
+        summary
+    }
+}
+
+// Pushes `line(i)` for each `i < len`, or for the first and last of them and a count of the
+// rest in between if there are more than `max`
+fn push_elided(
+    out: &mut String,
+    len: usize,
+    max: usize,
+    what: &str,
+    line: impl Fn(usize) -> String,
+) {
+    let (head, tail) = if len > max {
+        (max.div_ceil(2), max / 2)
+    } else {
+        (len, 0)
+    };
+    (0..head).for_each(|i| out.push_str(&line(i)));
+    if len > max {
+        out.push_str(&format!("  ... {} more {}\n", len - max, what));
+    }
+    (len - tail..len).for_each(|i| out.push_str(&line(i)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn even_leaf_tree() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+        println!("{}", tree);
+        assert_eq!(tree.hashes.len(), 3);
+        assert_eq!(tree.hashes[0].len(), 1);
+        assert_eq!(tree.hashes[1].len(), 2);
+        assert_eq!(tree.hashes[2].len(), 4);
+    }
+
+    #[test]
+    fn odd_leaf_tree() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+            "e".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+        println!("{}", tree);
+        assert_eq!(tree.hashes.len(), 4);
+        assert_eq!(tree.hashes[0].len(), 1);
+        assert_eq!(tree.hashes[1].len(), 2);
+        assert_eq!(tree.hashes[2].len(), 3);
+        assert_eq!(tree.hashes[3].len(), 5);
+    }
+
+    #[test]
+    fn test_different_value() {
+        let tree1 = MerkleTree::new(vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+            "e".to_string(),
+        ]);
+        let tree2 = MerkleTree::new(vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+            "f".to_string(),
+        ]);
+        let tree3 = MerkleTree::new(vec![
+            "b".to_string(),
+            "a".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+            "e".to_string(),
+        ]);
+        assert_ne!(tree1.root_hash(), tree2.root_hash());
+        assert_ne!(tree1.root_hash(), tree3.root_hash());
+        assert_ne!(tree2.root_hash(), tree3.root_hash());
+    }
+
+    #[test]
+    fn proof_siblings() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+        println!("{}", tree);
+
+        // Get a proof for "b" (index 1)
+        let proof = tree.get_proof(1);
+        println!(
+            "{:?}",
+            proof
+                .to_pairs()
+                .iter()
+                .map(|(is_left, hash)| (*is_left, hex::encode(hash)))
+                .collect::<Vec<(bool, String)>>()
+        );
+        // assert_eq!(proof.len(), 2);
+        // assert_eq!(proof[0], tree.hashes[2][1]);
+        // assert_eq!(proof[1], tree.hashes[2][2]);
+    }
+
+    #[test]
+    fn valid_proof() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+        println!("{}", tree);
+
+        // Get a proof for "b" (index 1)
+        let proof = tree.get_proof(1);
+        assert!(tree.prove("b".to_string(), proof));
+    }
+
+    #[test]
+    fn invalid_proof_wrong_element() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+        println!("{}", tree);
+
+        // Get a proof for "b" (index 1)
+        let proof = tree.get_proof(1);
+        // A proof for one leaf must not verify another
+        assert!(!tree.prove("a".to_string(), proof));
+    }
+
+    #[test]
+    fn invalid_proof_wrong_sibling() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+        println!("{}", tree);
+
+        // Get a proof for "b" (index 1)
+        let mut proof = tree.get_proof(1);
+        proof.siblings[0] = [0u8; 32];
+        // A proof with a corrupted sibling must not verify
+        assert!(!tree.prove("b".to_string(), proof));
+    }
+
+    #[test]
+    fn proof_bottom_to_top() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+
+        let proof = tree.proof(1).unwrap();
+        assert_eq!(proof, vec![tree.hashes[2][0], tree.hashes[1][1]]);
+        assert_eq!(proof.len(), tree.hashes.len() - 1);
+    }
+
+    #[test]
+    fn proof_odd_node_is_own_sibling() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+            "e".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves);
+
+        // "e" is the last node of an odd level, so it's paired with itself
+        let proof = tree.proof(4).unwrap();
+        assert_eq!(proof[0], tree.hashes[3][4]);
+        assert_eq!(proof[1], tree.hashes[2][2]);
+        assert_eq!(proof[2], tree.hashes[1][0]);
+        assert!(tree.prove("e".to_string(), tree.get_proof(4)));
+    }
+
+    #[test]
+    fn proof_out_of_bounds() {
+        let tree = MerkleTree::new(vec!["a".to_string(), "b".to_string()]);
+        assert!(tree.proof(2).is_none());
+    }
+
+    #[test]
+    fn verify_proof_against_root() {
+        let leaves = vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+            "e".to_string(),
+        ];
+        let tree = MerkleTree::new(leaves.clone());
+        let root = tree.root_hash();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.get_proof(i).to_pairs();
+            assert!(verify_proof(leaf, &proof, root, HashAlgo::Keccak256));
+        }
+
+        // Flipping a direction flag must break the proof
+        let mut proof = tree.get_proof(1).to_pairs();
+        proof[0].0 = !proof[0].0;
+        assert!(!verify_proof("b", &proof, root, HashAlgo::Keccak256));
+        assert!(!verify_proof(
+            "x",
+            &tree.get_proof(1).to_pairs(),
+            root,
+            HashAlgo::Keccak256
+        ));
+    }
+
+    #[test]
+    fn byte_leaves_match_string_leaves() {
+        let strings = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let bytes: Vec<Vec<u8>> = strings.iter().map(|s| s.as_bytes().to_vec()).collect();
+        let string_tree = MerkleTree::new(strings);
+        let byte_tree = MerkleTree::new(bytes.clone());
+        assert_eq!(string_tree.root_hash(), byte_tree.root_hash());
+
+        let proof = byte_tree.get_proof(2);
+        assert!(byte_tree.prove(bytes[2].clone(), proof));
+    }
+
+    #[test]
+    fn display_non_string_leaves_as_hex() {
+        let tree = MerkleTree::new(vec![vec![0xde, 0xad], vec![0xbe, 0xef]]);
+        let output = tree.to_string();
+        assert!(output.contains("  0: dead\n"));
+        assert!(output.contains("  1: beef\n"));
+    }
+
+    #[test]
+    fn custom_hasher() {
+        // Keccak-256 with the output reversed, so roots differ from the default hasher
+        struct ReversedKeccak;
+        impl MerkleHasher for ReversedKeccak {
+            fn digest(data: &[u8]) -> [u8; 32] {
+                let mut hash = Keccak256::digest(data);
+                hash.reverse();
+                hash
+            }
+        }
+
+        let leaves = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let tree = GenericMerkleTree::<_, ReversedKeccak>::new(leaves.clone());
+        assert_eq!(
+            tree.hashes,
+            build_with_hasher::<ReversedKeccak, _, 32>(leaves.clone())
+        );
+        assert_ne!(tree.root_hash(), MerkleTree::new(leaves).root_hash());
+        assert!(tree.prove("b".to_string(), tree.get_proof(1)));
+    }
+
+    #[test]
+    fn empty_tree() {
+        assert_eq!(
+            MerkleTree::<String>::try_new(vec![]).unwrap_err(),
+            MerkleError::EmptyTree
+        );
+        assert!(build::<String>(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_panics_on_empty() {
+        MerkleTree::<String>::new(vec![]);
+    }
+
+    #[test]
+    fn depth_and_leaf_count() {
+        let tree = MerkleTree::new(vec!["a".to_string()]);
+        assert_eq!(tree.depth(), 1);
+        assert_eq!(tree.leaf_count(), 1);
+
+        let tree = MerkleTree::new(vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(tree.depth(), 4);
+        assert_eq!(tree.leaf_count(), 5);
+    }
+
+    #[test]
+    fn sorted_pair_tree() {
+        let leaves = vec!["b", "a", "c", "d", "e"];
+        let tree = MerkleTree::new_sorted(leaves.clone());
+        assert_eq!(tree.hashes, build_sorted(leaves.clone()));
+        assert_ne!(
+            tree.root_hash(),
+            MerkleTree::new(leaves.clone()).root_hash()
+        );
+
+        let (a, b) = (Keccak256::digest(b"a"), Keccak256::digest(b"b"));
+        let (min, max) = if a < b { (a, b) } else { (b, a) };
+        assert_eq!(tree.hashes[2][0], Keccak256::digest(&[min, max].concat()));
+
+        // Directions are irrelevant once pairs are sorted, so flipping every bit of the index
+        // still proves
+        for (i, leaf) in leaves.iter().enumerate() {
+            let mut proof = tree.get_proof(i);
+            assert!(tree.prove(leaf, proof.clone()));
+            proof.leaf_index = !proof.leaf_index;
+            assert!(tree.prove(leaf, proof));
+        }
+    }
+
+    #[test]
+    fn root_hex_formats() {
+        let tree = MerkleTree::new(vec!["a", "b"]);
+        assert_eq!(tree.root_hex(), hex::encode(tree.root_hash()));
+        assert_eq!(tree.root_hex_prefixed(), format!("0x{}", tree.root_hex()));
+        assert_eq!(tree.root_hex().len(), 64);
+    }
+
+    #[test]
+    fn push_matches_rebuild() {
+        let leaves: Vec<String> = (0..17).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(vec![leaves[0].clone()]);
+        for n in 1..leaves.len() {
+            tree.push(leaves[n].clone()).unwrap();
+            let rebuilt = MerkleTree::new(leaves[..=n].to_vec());
+            assert_eq!(tree.root_hash(), rebuilt.root_hash());
+            assert_eq!(tree.hashes, rebuilt.hashes);
+        }
+
+        let mut sorted = MerkleTree::new_sorted(vec!["a", "b", "c"]);
+        sorted.push("d").unwrap();
+        assert_eq!(sorted.hashes, build_sorted(vec!["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn update_leaf_matches_rebuild() {
+        let mut leaves: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(leaves.clone());
+
+        for index in [0, 3, 6] {
+            tree.update_leaf(index, "changed".to_string()).unwrap();
+            leaves[index] = "changed".to_string();
+            let rebuilt = MerkleTree::new(leaves.clone());
+            assert_eq!(tree.root_hash(), rebuilt.root_hash());
+            assert_eq!(tree.hashes, rebuilt.hashes);
+        }
+
+        assert_eq!(
+            tree.update_leaf(7, "x".to_string()),
+            Err(MerkleError::IndexOutOfBounds { index: 7, len: 7 })
+        );
+    }
+
+    #[test]
+    fn leaves_accessor() {
+        let mut tree = MerkleTree::new(vec!["a".to_string(), "b".to_string()]);
+        tree.push("c".to_string()).unwrap();
+        assert_eq!(tree.leaves(), ["a", "b", "c"]);
+        assert_eq!(tree.leaves().iter().count(), tree.leaf_count());
+    }
+
+    #[test]
+    fn contains_and_index_of() {
+        let tree = MerkleTree::new(vec!["a", "b", "c", "b"]);
+        assert!(tree.contains("c"));
+        assert!(!tree.contains("d"));
+        assert_eq!(tree.index_of("a"), Some(0));
+        assert_eq!(tree.index_of("b"), Some(1));
+        assert_eq!(tree.index_of("d"), None);
+
+        // Matching is by hash, so equivalent bytes match a string leaf
+        assert_eq!(tree.index_of(&b"c".to_vec()), Some(2));
+    }
+
+    #[test]
+    fn pad_zero_odd_strategy() {
+        let leaves = vec!["a", "b", "c"];
+        assert_eq!(
+            build_with(leaves.clone(), OddStrategy::DuplicateLast),
+            build(&leaves)
+        );
+
+        let tree = MerkleTree::new_with(leaves.clone(), OddStrategy::PadZero);
+        let c = Keccak256::digest(b"c");
+        assert_eq!(
+            tree.hashes[1][1],
+            Keccak256::digest(&[c, [0u8; 32]].concat())
+        );
+        assert_eq!(
+            tree.hashes,
+            build_with(leaves.clone(), OddStrategy::PadZero)
+        );
+        assert_ne!(
+            tree.root_hash(),
+            MerkleTree::new(leaves.clone()).root_hash()
+        );
+
+        assert_eq!(tree.proof(2).unwrap()[0], [0u8; 32]);
+        assert!(tree.prove("c", tree.get_proof(2)));
+
+        let mut pushed = MerkleTree::new_with(vec!["a", "b"], OddStrategy::PadZero);
+        pushed.push("c").unwrap();
+        assert_eq!(pushed.hashes, tree.hashes);
+    }
+
+    #[test]
+    fn tagged_tree() {
+        let leaves = vec!["a", "b", "c"];
+        let tree = MerkleTree::new_tagged(leaves.clone(), 0x00, 0x01);
+        assert_eq!(tree.hashes, build_tagged(leaves.clone(), 0x00, 0x01));
+        assert_ne!(
+            tree.root_hash(),
+            MerkleTree::new(leaves.clone()).root_hash()
+        );
+
+        let (a, b) = (Keccak256::digest(b"\x00a"), Keccak256::digest(b"\x00b"));
+        assert_eq!(tree.hashes[2][0], a);
+        assert_eq!(
+            tree.hashes[1][0],
+            Keccak256::digest(&[&[0x01], a.as_slice(), b.as_slice()].concat())
+        );
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert!(tree.prove(leaf, tree.get_proof(i)));
+        }
+        // An untagged verifier can't accept proofs from a tagged tree
+        assert!(!verify_proof(
+            "a",
+            &tree.get_proof(0).to_pairs(),
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+    }
+
+    #[test]
+    fn get_node_is_root_first() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.get_node(0, 0), Some(tree.root_hash()));
+        assert_eq!(tree.get_node(2, 2), Some(Keccak256::digest(b"c")));
+        assert_eq!(tree.get_node(0, 1), None);
+        assert_eq!(tree.get_node(2, 3), None);
+        assert_eq!(tree.get_node(3, 0), None);
+    }
+
+    #[test]
+    fn tree_from_hashes() {
+        let leaves = vec!["a", "b", "c"];
+        let leaf_hashes: Vec<[u8; 32]> = leaves
+            .iter()
+            .map(|l| Keccak256::digest(l.as_bytes()))
+            .collect();
+        let tree = MerkleTree::from_hashes(leaf_hashes.clone());
+        assert_eq!(tree.root_hash(), MerkleTree::new(leaves).root_hash());
+        assert_eq!(tree.hashes, build_from_hashes(leaf_hashes.clone()));
+
+        assert_eq!(MerkleTree::from(leaf_hashes.clone()).hashes, tree.hashes);
+        assert!(tree.prove(leaf_hashes[1], tree.get_proof(1)));
+        assert_eq!(tree.index_of(&leaf_hashes[2]), Some(2));
+        // Lookups take leaf hashes, so a value is never hashed, whatever its length
+        assert_eq!(tree.index_of("c"), None);
+        let lookalike = MerkleTree::from_hashes(vec![*b"0123456789abcdef0123456789abcdef"]);
+        assert_eq!(
+            lookalike.index_of("0123456789abcdef0123456789abcdef"),
+            Some(0)
+        );
+        assert!(tree
+            .to_string()
+            .contains(&format!("  0: {}\n", hex::encode(leaf_hashes[0]))));
+    }
+
+    #[test]
+    fn proof_for_byte_leaf() {
+        let leaves = vec![
+            b"deadbeef".to_vec(),
+            vec![0xde, 0xad, 0xbe, 0xef],
+            vec![0x00],
+        ];
+        let tree = MerkleTree::new(leaves.clone());
+
+        let proof = tree.proof_for_bytes(b"deadbeef").unwrap();
+        assert_eq!(proof, tree.proof(0).unwrap());
+        let pairs: Vec<(bool, [u8; 32])> = tree.get_proof(0).to_pairs();
+        assert_eq!(pairs.iter().map(|p| p.1).collect::<Vec<_>>(), proof);
+        assert!(verify_proof(
+            &leaves[0],
+            &pairs,
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+
+        // The raw bytes and their hex-decoded form are different leaves
+        assert_eq!(
+            tree.proof_for_bytes(&[0xde, 0xad, 0xbe, 0xef]),
+            tree.proof(1)
+        );
+        assert!(tree.proof_for_bytes(b"missing").is_none());
+    }
+
+    #[test]
+    fn tree_equality() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        assert_eq!(tree, MerkleTree::new(vec!["a", "b", "c"]));
+        assert_ne!(tree, MerkleTree::new(vec!["a", "c", "b"]));
+        assert_ne!(tree, MerkleTree::new(vec!["a", "b"]));
+        assert_ne!(
+            tree,
+            MerkleTree::new_with(vec!["a", "b", "c"], OddStrategy::PadZero)
+        );
+
+        let mut pushed = MerkleTree::new(vec!["a", "b"]);
+        pushed.push("c").unwrap();
+        assert_eq!(tree, pushed);
+    }
+
+    #[test]
+    fn verify_self_detects_tampering() {
+        let mut tree = MerkleTree::new(vec!["a", "b", "c", "d", "e"]);
+        assert!(tree.verify_self());
+        tree.update_leaf(2, "x").unwrap();
+        tree.push("f").unwrap();
+        assert!(tree.verify_self());
+
+        let mut tampered = MerkleTree::new(vec!["a", "b", "c"]);
+        tampered.hashes[1][0] = [0u8; 32];
+        assert!(!tampered.verify_self());
+
+        let mut tampered = MerkleTree::new(vec!["a", "b", "c"]);
+        tampered.leaves[0] = "z";
+        assert!(!tampered.verify_self());
+    }
+
+    #[test]
+    fn compute_root_matches_tree() {
+        for n in [1, 2, 3, 7, 8, 33] {
+            let leaves: Vec<String> = (0..n).map(|i| i.to_string()).collect();
+            assert_eq!(
+                compute_root(&leaves),
+                MerkleTree::new(leaves.clone()).root_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_digest() {
+        let tree =
+            GenericMerkleTree::<&str, TruncatedKeccak256, 20>::new(vec!["a", "b", "c", "d", "e"]);
+        let root: [u8; 20] = tree.root_hash();
+        let full = MerkleTree::new(vec!["a"]).root_hash();
+        assert_eq!(tree.hashes[tree.depth() - 1][0], full[..20]);
+        assert_ne!(root, [0u8; 20]);
+
+        let proof = tree.get_proof(2);
+        assert!(tree.prove("c", proof));
+    }
+
+    #[test]
+    fn wide_digest() {
+        // Two Keccak-256 digests side by side, standing in for a 64-byte hash
+        struct DoubleKeccak;
+        impl MerkleHasher<64> for DoubleKeccak {
+            fn digest(data: &[u8]) -> [u8; 64] {
+                let first = Keccak256::digest(data);
+                let mut hash = [0u8; 64];
+                hash[..32].copy_from_slice(&first);
+                hash[32..].copy_from_slice(&Keccak256::digest(&first));
+                hash
+            }
+        }
+
+        let tree = GenericMerkleTree::<&str, DoubleKeccak, 64>::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.root_hex().len(), 128);
+        assert!(tree.prove("a", tree.get_proof(0)));
+        assert!(!tree.prove("b", tree.get_proof(0)));
+    }
+
+    #[test]
+    fn proof_for_value() {
+        let tree = MerkleTree::new(vec!["a", "b", "c", "b"]);
+        assert_eq!(tree.proof_for_value("b"), Some((1, tree.proof(1).unwrap())));
+        assert_eq!(tree.proof_for_value("c"), Some((2, tree.proof(2).unwrap())));
+        assert!(tree.proof_for_value("z").is_none());
+    }
+
+    #[test]
+    fn dedup() {
+        let leaves = vec!["a", "b", "a", "c", "b"];
+        let tree = MerkleTree::new(leaves.clone());
+        assert!(tree.has_duplicates());
+
+        let deduped = MerkleTree::new_deduped(leaves);
+        assert!(!deduped.has_duplicates());
+        assert_eq!(deduped.leaves(), ["a", "b", "c"]);
+        assert_eq!(deduped, MerkleTree::new(vec!["a", "b", "c"]));
+        assert_ne!(deduped.root_hash(), tree.root_hash());
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let tree = MerkleTree::new(vec!["a".to_string(), "b".to_string()]);
+        let mut cloned = tree.clone();
+        assert_eq!(cloned, tree);
+        cloned.push("c".to_string()).unwrap();
+        assert_ne!(cloned, tree);
+        assert_eq!(tree.leaf_count(), 2);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn build_borrows() {
+        let leaves = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let levels = build(&leaves);
+        assert_eq!(levels, MerkleTree::new(leaves.clone()).hashes);
+        assert_eq!(levels, build_owned(leaves));
+    }
+
+    #[test]
+    fn algo_is_checked() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.algo(), HashAlgo::Keccak256);
+        assert_eq!(
+            GenericMerkleTree::<_, TruncatedKeccak256, 20>::new(vec!["a"]).algo(),
+            HashAlgo::Custom
+        );
+
+        let proof = tree.get_proof(1).to_pairs();
+        assert!(verify_proof(
+            "b",
+            &proof,
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+        for algo in [
+            HashAlgo::Keccak512,
+            HashAlgo::Sha256,
+            HashAlgo::Blake3,
+            HashAlgo::Custom,
+        ] {
+            assert!(!verify_proof("b", &proof, tree.root_hash(), algo));
+        }
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn sha256_trees() {
+        let tree = GenericMerkleTree::<_, Sha256>::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.algo(), HashAlgo::Sha256);
+        // SHA-256("a")
+        assert_eq!(
+            hex::encode(tree.hashes[tree.depth() - 1][0]),
+            "ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb"
+        );
+
+        let proof = tree.get_proof(2).to_pairs();
+        assert!(verify_proof(
+            "c",
+            &proof,
+            tree.root_hash(),
+            HashAlgo::Sha256
+        ));
+        assert!(!verify_proof(
+            "c",
+            &proof,
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3_trees() {
+        let tree = GenericMerkleTree::<_, Blake3>::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.algo(), HashAlgo::Blake3);
+
+        let proof = tree.get_proof(0).to_pairs();
+        assert!(verify_proof(
+            "a",
+            &proof,
+            tree.root_hash(),
+            HashAlgo::Blake3
+        ));
+        assert!(!verify_proof(
+            "a",
+            &proof,
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+    }
+
+    #[test]
+    fn append_many_matches_rebuild() {
+        let leaves: Vec<String> = (0..40).map(|i| i.to_string()).collect();
+        for (initial, batch) in [(1, 1), (3, 2), (5, 11), (8, 8), (17, 23)] {
+            let mut tree = MerkleTree::new(leaves[..initial].to_vec());
+            tree.append_many(leaves[initial..initial + batch].to_vec())
+                .unwrap();
+            assert_eq!(tree, MerkleTree::new(leaves[..initial + batch].to_vec()));
+        }
+
+        let mut tree = MerkleTree::new(vec!["a".to_string()]);
+        tree.append_many(vec![]).unwrap();
+        assert_eq!(tree, MerkleTree::new(vec!["a".to_string()]));
+    }
+
+    // A small xorshift generator, so the randomized tests below are reproducible
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn verify_proof_random_inputs() {
+        let tree = MerkleTree::new((0..13).map(|i| i.to_string()).collect());
+        let root = tree.root_hash();
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+
+        for _ in 0..500 {
+            let len = (xorshift(&mut state) % 80) as usize;
+            let proof: Vec<(bool, [u8; 32])> = (0..len)
+                .map(|_| {
+                    let mut sibling = [0u8; 32];
+                    for chunk in sibling.chunks_mut(8) {
+                        chunk.copy_from_slice(&xorshift(&mut state).to_le_bytes());
+                    }
+                    (xorshift(&mut state).is_multiple_of(2), sibling)
+                })
+                .collect();
+            let leaf: Vec<u8> = (0..xorshift(&mut state) % 40)
+                .map(|_| xorshift(&mut state) as u8)
+                .collect();
+            assert!(!verify_proof(&leaf, &proof, root, HashAlgo::Keccak256));
+        }
+
+        // Genuine proofs still verify, but not once truncated, extended or flipped
+        let proof = tree.get_proof(12).to_pairs();
+        assert!(verify_proof("12", &proof, root, HashAlgo::Keccak256));
+        assert!(!verify_proof("12", &proof[1..], root, HashAlgo::Keccak256));
+        let mut extended = proof.clone();
+        extended.push((false, root));
+        assert!(!verify_proof("12", &extended, root, HashAlgo::Keccak256));
+        let mut flipped = proof.clone();
+        // Leaf 12's first two siblings are duplicates of itself, so flip the third
+        flipped[2].0 = !flipped[2].0;
+        assert!(!verify_proof("12", &flipped, root, HashAlgo::Keccak256));
+        assert!(!verify_proof("12", &[], root, HashAlgo::Keccak256));
+        assert!(!verify_proof(
+            "12",
+            &vec![(false, root); MAX_PROOF_LEN + 1],
+            root,
+            HashAlgo::Keccak256
+        ));
+    }
+
+    #[test]
+    fn level_shape() {
+        let tree = MerkleTree::new(vec!["a", "b", "c", "d", "e"]);
+        let widths: Vec<usize> = (0..tree.depth())
+            .map(|l| tree.width_at(l).unwrap())
+            .collect();
+        assert_eq!(widths, [1, 2, 3, 5]);
+        assert_eq!(tree.width_at(4), None);
+
+        let levels: Vec<&[[u8; 32]]> = tree.levels().collect();
+        assert_eq!(levels[0], [tree.root_hash()]);
+        assert_eq!(levels.iter().map(|l| l.len()).collect::<Vec<_>>(), widths);
+    }
+
+    #[test]
+    fn hex_leaf_encoding() {
+        let tree =
+            MerkleTree::new_with_encoding(vec!["0xdeadbeef", "CAFE", "0X00"], LeafEncoding::Hex)
+                .unwrap();
+        let raw = MerkleTree::new(vec![
+            vec![0xde, 0xad, 0xbe, 0xef],
+            vec![0xca, 0xfe],
+            vec![0x00],
+        ]);
+        assert_eq!(tree.hashes, raw.hashes);
+        assert_eq!(
+            build_with_encoding(vec!["0xdeadbeef", "CAFE", "0X00"], LeafEncoding::Hex),
+            Ok(raw.hashes.to_nested())
+        );
+        assert_eq!(
+            build_with_encoding(vec!["0xdeadbeef", "CAFE"], LeafEncoding::Utf8),
+            Ok(build(&["0xdeadbeef", "CAFE"]))
+        );
+        assert_eq!(tree.index_of("cafe"), Some(1));
+        assert!(tree.prove("0xdeadbeef", tree.get_proof(0)));
+
+        assert_eq!(
+            MerkleTree::new_with_encoding(vec!["00", "0xzz"], LeafEncoding::Hex).unwrap_err(),
+            MerkleError::InvalidHex { index: 1 }
+        );
+        let mut tree = tree;
+        assert_eq!(
+            tree.update_leaf(2, "abc"),
+            Err(MerkleError::InvalidHex { index: 2 })
+        );
+    }
+
+    #[test]
+    fn debug_shows_hex() {
+        let tree = MerkleTree::new(vec!["a", "b"]);
+        let debug = format!("{:?}", tree);
+        assert!(debug.starts_with(&format!("GenericMerkleTree {{ root: {}, ", tree.root_hex())));
+        assert!(debug.contains(&MerkleTree::new(vec!["a"]).root_hex()));
+        assert!(!debug.contains("[58, "));
+    }
+
+    #[test]
+    fn root_comparisons() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        assert!(tree.has_root(&tree.root_hash()));
+        assert!(!tree.has_root(&[0u8; 32]));
+
+        assert!(tree.has_root_hex(&tree.root_hex()));
+        assert!(tree.has_root_hex(&tree.root_hex_prefixed()));
+        assert!(tree.has_root_hex(&tree.root_hex().to_uppercase()));
+        assert!(!tree.has_root_hex(&tree.root_hex()[2..]));
+        assert!(!tree.has_root_hex("0xnot hex"));
+        assert!(!tree.has_root_hex(""));
+    }
+
+    #[test]
+    fn leaf_hashes() {
+        let tree = MerkleTree::new_tagged(vec!["a", "b", "c"], 0x00, 0x01);
+        assert_eq!(tree.leaf_hash(1), Some(Keccak256::digest(b"\x00b")));
+        assert_eq!(tree.leaf_hash(3), None);
+    }
+
+    #[test]
+    fn try_root_hash() {
+        let tree = MerkleTree::new(vec!["a", "b"]);
+        assert_eq!(tree.try_root_hash(), Some(tree.root_hash()));
+
+        let empty = MerkleTree::<&str>::from_parts(
+            vec![],
+            build_levels::<Keccak256, &str, 32>(&[], Scheme::default()),
+            Scheme::default(),
+        );
+        assert_eq!(empty.try_root_hash(), None);
+    }
+
+    #[test]
+    fn node_encodings() {
+        let leaves = vec!["a", "b", "c"];
+        let raw = build_with_node_encoding(leaves.clone(), NodeEncoding::Raw);
+        assert_eq!(raw, build(&leaves));
+
+        let separated = build_with_node_encoding(leaves.clone(), NodeEncoding::Separator(0xff));
+        let [a, b] = [separated[2][0], separated[2][1]];
+        assert_eq!(
+            separated[1][0],
+            Keccak256::digest(&[&a[..], &[0xff], &b[..]].concat())
+        );
+
+        let prefixed = build_with_node_encoding(leaves.clone(), NodeEncoding::LengthPrefixed);
+        let len = [0, 0, 0, 32];
+        assert_eq!(
+            prefixed[1][0],
+            Keccak256::digest(&[&len[..], &a[..], &len[..], &b[..]].concat())
+        );
+
+        assert_ne!(raw[0], separated[0]);
+        assert_ne!(raw[0], prefixed[0]);
+        let tree = MerkleTree::new_with_node_encoding(leaves, NodeEncoding::Separator(0xff));
+        assert_eq!(tree.hashes, separated);
+        assert!(tree.prove("c", tree.get_proof(2)));
+    }
+
+    #[test]
+    fn proof_lengths() {
+        for n in [1, 2, 5, 8, 9] {
+            let tree = MerkleTree::new((0..n).map(|i| i.to_string()).collect());
+            for i in 0..n {
+                let len = tree.proof(i).unwrap().len();
+                assert_eq!(tree.expected_proof_len(i), Some(len));
+                assert_eq!(tree.proof_size(), len);
+            }
+            assert_eq!(tree.expected_proof_len(n), None);
+        }
+    }
+
+    #[test]
+    fn update_many_matches_rebuild() {
+        let mut leaves: Vec<String> = (0..23).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(leaves.clone());
+        let changes: Vec<(usize, String)> = [3, 4, 5, 22, 0, 4]
+            .iter()
+            .enumerate()
+            .map(|(n, i)| (*i, format!("changed {}", n)))
+            .collect();
+
+        tree.update_many(&changes).unwrap();
+        for (index, leaf) in &changes {
+            leaves[*index] = leaf.clone();
+        }
+        assert_eq!(tree, MerkleTree::new(leaves.clone()));
+
+        let before = tree.clone();
+        assert_eq!(
+            tree.update_many(&[(1, "x".to_string()), (23, "y".to_string())]),
+            Err(MerkleError::IndexOutOfBounds { index: 23, len: 23 })
+        );
+        assert_eq!(tree, before);
+    }
+
+    #[test]
+    fn collect_into_tree() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let tree: MerkleTree = leaves.iter().cloned().collect();
+        assert_eq!(tree, MerkleTree::new(leaves));
+    }
+
+    #[test]
+    fn navigate_levels() {
+        for n in [4, 5, 7, 8] {
+            let tree = MerkleTree::new((0..n).map(|i| i.to_string()).collect());
+            for level in 0..tree.depth() - 1 {
+                for index in 0..tree.width_at(level).unwrap() {
+                    let (left, right) = tree.children_indices(level, index);
+                    assert_eq!(tree.parent_index(level + 1, left), (level, index));
+                    let left_hash = tree.get_node(level + 1, left).unwrap();
+                    let right_hash = match right {
+                        Some(right) => {
+                            assert_eq!(tree.parent_index(level + 1, right), (level, index));
+                            tree.get_node(level + 1, right).unwrap()
+                        }
+                        None => left_hash,
+                    };
+                    assert_eq!(
+                        tree.get_node(level, index).unwrap(),
+                        Keccak256::digest(&[left_hash, right_hash].concat())
+                    );
+                }
+            }
+        }
+
+        // Five leaves: the root is over (0 1 2 3) and (4 4), whose only child is leaf 4
+        let tree = MerkleTree::new((0..5).map(|i| i.to_string()).collect());
+        assert_eq!(tree.children_indices(0, 0), (0, Some(1)));
+        assert_eq!(tree.children_indices(1, 1), (2, None));
+        assert_eq!(tree.children_indices(2, 2), (4, None));
+        assert_eq!(tree.parent_index(3, 4), (2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "the root has no parent")]
+    fn root_has_no_parent() {
+        MerkleTree::new(vec!["a".to_string(), "b".to_string()]).parent_index(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a leaf has no children")]
+    fn leaf_has_no_children() {
+        MerkleTree::new(vec!["a".to_string(), "b".to_string()]).children_indices(1, 1);
+    }
+
+    #[test]
+    fn display_summary() {
+        let small = MerkleTree::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert_eq!(small.display_summary(4), small.to_string());
+
+        let tree = MerkleTree::new((0..1000).map(|i| i.to_string()).collect());
+        let summary = tree.display_summary(4);
+        assert_eq!(summary.lines().count(), 24);
+        assert!(summary.contains("  0: 0\n  1: 1\n  ... 996 more leaves\n  998: 998\n"));
+        assert!(summary.contains("  ... 7 more levels\n"));
+        assert!(summary.contains("  ... 996 more hashes\n"));
+        assert!(summary.ends_with(&format!(
+            "Root Hash:\n  {}\n",
+            hex::encode(tree.root_hash())
+        )));
+        assert!(!summary.contains("Level 5:"));
+    }
+
+    #[test]
+    fn merge() {
+        let leaves: Vec<String> = (0..16).map(|i| i.to_string()).collect();
+        for (split, total) in [(1, 2), (4, 8), (4, 7), (8, 9), (8, 11), (2, 3)] {
+            let left = MerkleTree::new(leaves[..split].to_vec());
+            let right = MerkleTree::new(leaves[split..total].to_vec());
+            let roots = (left.root_hash(), right.root_hash());
+            let same_depth = left.depth() == right.depth();
+
+            let merged = MerkleTree::merge(left, right);
+            assert_eq!(merged, MerkleTree::new(leaves[..total].to_vec()));
+            assert!(merged.verify_self());
+            if same_depth {
+                assert_eq!(
+                    merged.root_hash(),
+                    Keccak256::digest(&[roots.0, roots.1].concat())
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't a power of two")]
+    fn merge_onto_incomplete_tree() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        MerkleTree::merge(
+            MerkleTree::new(leaves[..3].to_vec()),
+            MerkleTree::new(leaves[3..].to_vec()),
+        );
+    }
+
+    #[test]
+    fn from_hex_leaves() {
+        let hashes: Vec<[u8; 32]> = (0..5u8).map(|i| Keccak256::digest(&[i])).collect();
+        let mut hexes: Vec<String> = hashes.iter().map(hex::encode).collect();
+        hexes[1] = format!("0x{}", hexes[1].to_uppercase());
+        let tree = MerkleTree::from_hex_leaves(hexes.clone()).unwrap();
+        assert_eq!(tree, MerkleTree::from_hashes(hashes));
+
+        hexes[3].pop();
+        assert_eq!(
+            MerkleTree::from_hex_leaves(hexes.clone()),
+            Err(MerkleError::InvalidHex { index: 3 })
+        );
+        hexes[3].pop();
+        assert_eq!(
+            MerkleTree::from_hex_leaves(hexes),
+            Err(MerkleError::InvalidHashLength {
+                index: 3,
+                len: 31,
+                expected: 32
+            })
+        );
+        assert_eq!(
+            MerkleTree::from_hex_leaves(vec![]),
+            Err(MerkleError::EmptyTree)
+        );
+    }
+
+    #[test]
+    fn concurrent_proofs() {
+        let leaves: Vec<String> = (0..257).map(|i| i.to_string()).collect();
+        let tree = std::sync::Arc::new(MerkleTree::new(leaves.clone()));
+        let root = tree.root_hash();
+
+        let threads: Vec<_> = (0..16)
+            .map(|thread| {
+                let tree = std::sync::Arc::clone(&tree);
+                let leaves = leaves.clone();
+                std::thread::spawn(move || {
+                    for index in (thread..leaves.len()).step_by(16) {
+                        let pairs = tree.get_proof(index).to_pairs();
+                        assert_eq!(tree.root_hash(), root);
+                        assert!(verify_proof(
+                            &leaves[index],
+                            &pairs,
+                            root,
+                            HashAlgo::Keccak256
+                        ));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn leaves_and_proofs() {
+        let leaves: Vec<String> = (0..300).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(leaves.clone());
+        let exported = tree.leaves_and_proofs();
+        assert_eq!(exported.len(), 300);
+
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..30 {
+            let index = (xorshift(&mut state) % 300) as usize;
+            let (leaf, siblings) = &exported[index];
+            assert_eq!(*leaf, &leaves[index]);
+            assert_eq!(Some(siblings.clone()), tree.proof(index));
+
+            let pairs: Vec<(bool, [u8; 32])> = siblings
+                .iter()
+                .enumerate()
+                .map(|(height, sibling)| (index >> height & 1 == 1, *sibling))
+                .collect();
+            assert!(verify_proof(
+                *leaf,
+                &pairs,
+                tree.root_hash(),
+                HashAlgo::Keccak256
+            ));
+        }
+    }
+
+    #[test]
+    fn pop() {
+        let leaves: Vec<String> = (0..9).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(leaves.clone());
+        for len in (1..9).rev() {
+            assert_eq!(tree.pop(), Ok(Some(leaves[len].clone())));
+            assert_eq!(tree, MerkleTree::new(leaves[..len].to_vec()));
+        }
+        assert_eq!(tree.pop(), Ok(None));
+        assert_eq!(tree.leaf_count(), 1);
+
+        tree.push("1".to_string()).unwrap();
+        assert_eq!(tree, MerkleTree::new(leaves[..2].to_vec()));
+    }
+
+    #[test]
+    fn salted_leaves() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let salt = Keccak256::digest(b"salt");
+        let tree = MerkleTree::new_salted(leaves.clone(), salt);
+        assert_eq!(tree.salt(), Some(salt));
+        assert_eq!(MerkleTree::new(leaves.clone()).salt(), None);
+        assert_ne!(
+            tree.root_hash(),
+            MerkleTree::new(leaves.clone()).root_hash()
+        );
+        assert_eq!(
+            tree.leaf_hash(3),
+            Some(Keccak256::digest(&[&salt[..], b"3"].concat()))
+        );
+
+        let proof = tree.get_proof(3);
+        let salted = [&salt[..], leaves[3].as_bytes()].concat();
+        assert!(verify_proof(
+            &salted,
+            &proof.to_pairs(),
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+        assert!(!verify_proof(
+            &leaves[3],
+            &proof.to_pairs(),
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
+        assert!(tree.prove(leaves[3].clone(), proof));
+        assert_eq!(tree.index_of(&leaves[3]), Some(3));
+    }
+
+    #[test]
+    fn sorted_leaves() {
+        let leaves: Vec<String> = ["b", "c", "a", "10", "9"].map(String::from).to_vec();
+        let mut reversed = leaves.clone();
+        reversed.reverse();
+
+        let tree = MerkleTree::new_sorted_leaves(leaves.clone(), LeafOrder::Bytes);
+        assert_eq!(tree.leaves(), ["10", "9", "a", "b", "c"]);
+        assert_eq!(
+            tree,
+            MerkleTree::new_sorted_leaves(reversed.clone(), LeafOrder::Bytes)
+        );
+        assert_ne!(
+            tree.root_hash(),
+            MerkleTree::new(leaves.clone()).root_hash()
+        );
+
+        let by_hash = MerkleTree::new_sorted_leaves(leaves, LeafOrder::Hash);
+        assert!(by_hash
+            .levels()
+            .last()
+            .unwrap()
+            .windows(2)
+            .all(|pair| pair[0] <= pair[1]));
+        assert_eq!(
+            by_hash,
+            MerkleTree::new_sorted_leaves(reversed, LeafOrder::Hash)
+        );
+    }
+
+    #[test]
+    fn compact_proofs() {
+        let tree = MerkleTree::new((0..13).map(|i| i.to_string()).collect());
+        let root = tree.root_hash();
+        let compact = |pairs: &[(bool, [u8; 32])]| {
+            let directions = pairs
+                .iter()
+                .enumerate()
+                .filter(|(_, (is_left, _))| *is_left);
+            CompactProof {
+                directions: directions.fold(0, |directions, (i, _)| directions | 1 << i),
+                siblings: pairs.iter().map(|(_, hash)| *hash).collect(),
+            }
+        };
+
+        let mut state = 0x1234_5678_9abc_def0;
+        for index in 0..13 {
+            let proof = tree.get_proof(index);
+            let packed = proof.to_compact();
+            assert_eq!(Proof::from_compact(&packed, 13).as_ref(), Some(&proof));
+
+            let leaf = index.to_string();
+            let mut pairs = proof.to_pairs();
+            for _ in 0..20 {
+                assert_eq!(
+                    verify_compact(&leaf, &compact(&pairs), root, HashAlgo::Keccak256),
+                    verify_proof(&leaf, &pairs, root, HashAlgo::Keccak256)
+                );
+                if pairs.is_empty() {
+                    break;
+                }
+                // Flip a direction, corrupt a sibling, or drop one
+                let at = (xorshift(&mut state) as usize) % pairs.len();
+                match xorshift(&mut state) % 3 {
+                    0 => pairs[at].0 = !pairs[at].0,
+                    1 => pairs[at].1[0] ^= 1,
+                    _ => {
+                        pairs.remove(at);
+                    }
+                }
+            }
+        }
+
+        let mut stray = tree.get_proof(0).to_compact();
+        assert!(verify_compact("0", &stray, root, HashAlgo::Keccak256));
+        stray.directions |= 1 << stray.siblings.len();
+        assert!(!verify_compact("0", &stray, root, HashAlgo::Keccak256));
+        assert!(Proof::from_compact(&stray, 13).is_none());
+    }
+
+    #[test]
+    fn frozen_tree() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(leaves.clone());
+        assert!(!tree.is_frozen());
+        tree.freeze();
+        let root = tree.root_hash();
+
+        assert_eq!(tree.push("5".to_string()), Err(MerkleError::Frozen));
+        assert_eq!(tree.append_many(vec![]), Err(MerkleError::Frozen));
+        assert_eq!(tree.pop(), Err(MerkleError::Frozen));
+        assert_eq!(
+            tree.update_leaf(0, "x".to_string()),
+            Err(MerkleError::Frozen)
+        );
+        assert_eq!(tree.update_many(&[]), Err(MerkleError::Frozen));
+        assert_eq!(tree.root_hash(), root);
+        assert_eq!(tree, MerkleTree::new(leaves));
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LeftOrRight {
-    Left,
-    Right,
-}
+        let proof = tree.get_proof(2);
+        assert!(tree.prove("2".to_string(), proof));
+        assert!(tree.clone().is_frozen());
+    }
 
-#[derive(Debug, Clone)]
-pub struct Proof(Vec<([u8; 32], LeftOrRight)>);
+    #[test]
+    fn cached_root_follows_mutations() {
+        let mut tree = MerkleTree::new(vec!["a".to_string()]);
+        tree.push("b".to_string()).unwrap();
+        tree.append_many(vec!["c".to_string(), "d".to_string()])
+            .unwrap();
+        tree.update_leaf(1, "x".to_string()).unwrap();
+        tree.update_many(&[(0, "y".to_string())]).unwrap();
+        tree.pop().unwrap();
+        assert_eq!(Some(tree.root_hash()), tree.get_node(0, 0));
+        assert_eq!(tree.root_hash(), compute_root(&["y", "x", "c"]));
+    }
 
-impl MerkleTree {
-    #[allow(clippy::new_without_default)]
-    pub fn new(leaves: Vec<String>) -> Self {
-        let hashes = build(leaves.clone());
-        MerkleTree { leaves, hashes }
+    #[test]
+    fn single_leaf_is_its_own_root() {
+        let tree = MerkleTree::new(vec!["a".to_string()]);
+        assert_eq!(tree.root_hash(), Keccak256::digest(b"a"));
+        assert_eq!(tree.depth(), 1);
+        assert_eq!(build(&["a"]), vec![vec![Keccak256::digest(b"a")]]);
+
+        let proof = tree.get_proof(0).to_pairs();
+        assert!(proof.is_empty());
+        assert!(verify_proof(
+            "a",
+            &proof,
+            tree.root_hash(),
+            HashAlgo::Keccak256
+        ));
     }
 
-    pub fn root_hash(&self) -> [u8; 32] {
-        self.hashes[0][0]
+    #[test]
+    #[cfg(feature = "std")]
+    fn from_lines() {
+        let tree = MerkleTree::from_lines("a\nb\r\nc\n".as_bytes()).unwrap();
+        assert_eq!(
+            tree,
+            MerkleTree::new(["a", "b", "c"].map(String::from).to_vec())
+        );
+
+        let error = MerkleTree::from_lines("".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            error.into_inner().unwrap().downcast_ref::<MerkleError>(),
+            Some(&MerkleError::EmptyTree)
+        );
+
+        let error = MerkleTree::from_lines(&b"a\n\xff\n"[..]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
     }
 
-    pub fn get_proof(&self, leaf_index: usize) -> Proof {
-        let mut proof = vec![];
-        let mut index = leaf_index;
+    #[test]
+    fn compute_root_from_proof() {
+        let tree = MerkleTree::new((0..7).map(|i| i.to_string()).collect());
+        for index in 0..7 {
+            let proof = tree.get_proof(index).to_pairs();
+            let leaf = index.to_string();
+            assert_eq!(
+                super::compute_root_from_proof(&leaf, &proof),
+                tree.root_hash()
+            );
+            assert_ne!(
+                super::compute_root_from_proof("x", &proof),
+                tree.root_hash()
+            );
+        }
+    }
 
-        // Start at the leaves and work our way up to the root
-        for level in self.hashes.iter().skip(1).rev() {
-            let (sibling_parity, sibling_index) = if index % 2 == 0 {
-                (LeftOrRight::Right, index + 1)
-            } else {
-                (LeftOrRight::Left, index - 1)
-            };
-            proof.push((level[sibling_index], sibling_parity));
-            index /= 2;
+    #[test]
+    fn public_hashing_matches_build() {
+        let levels = build(&["a", "b", "c"]);
+        let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+            .iter()
+            .map(|leaf| hash_leaf(leaf.as_bytes()))
+            .collect();
+        assert_eq!(levels[2], leaves);
+        assert_eq!(levels[1][0], hash_nodes(&leaves[0], &leaves[1]));
+        assert_eq!(levels[1][1], hash_nodes(&leaves[2], &leaves[2]));
+        assert_eq!(levels[0][0], hash_nodes(&levels[1][0], &levels[1][1]));
+        // The empty-input Keccak-256 digest, which differs from SHA3-256's
+        assert_eq!(
+            hex::encode(hash_leaf(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[test]
+    fn self_describing_proofs() {
+        for n in [1, 2, 5, 8, 13] {
+            let leaves: Vec<String> = (0..n).map(|i| i.to_string()).collect();
+            let tree = MerkleTree::new(leaves.clone());
+            let root = tree.root_hash();
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.get_proof(i);
+                assert_eq!((proof.leaf_index, proof.leaf_count), (i, n));
+                assert!(proof.verify(leaf, root));
+                assert!(!proof.verify("x", root));
+            }
         }
-        Proof(proof)
+
+        let tree = MerkleTree::new((0..5).map(|i| i.to_string()).collect());
+        let proof = tree.get_proof(4);
+        assert!(proof.verify("4", tree.root_hash()));
+        let mut moved = tree.get_proof(1);
+        moved.leaf_index = 0;
+        assert!(!moved.verify("1", tree.root_hash()));
+        let mut past_the_end = proof.clone();
+        past_the_end.leaf_index = 5;
+        assert!(!past_the_end.verify("4", tree.root_hash()));
+        let mut wrong_size = proof.clone();
+        wrong_size.leaf_count = 9;
+        assert!(!wrong_size.verify("4", tree.root_hash()));
+        let mut empty = proof;
+        empty.leaf_count = 0;
+        assert!(!empty.verify("4", tree.root_hash()));
     }
 
-    pub fn prove(&self, value: String, proof: Proof) -> bool {
-        let mut hash = keccak(value.as_bytes());
+    #[test]
+    fn chunked_data() {
+        let data: Vec<u8> = (0..10 * 1024).map(|i| (i % 251) as u8).collect();
+        let tree = MerkleTree::from_chunks(&data, 1024).unwrap();
+        assert_eq!(tree.leaf_count(), 10);
+        assert_eq!(
+            tree.root_hash(),
+            MerkleTree::new(data.chunks(1024).collect()).root_hash()
+        );
+        assert!(tree
+            .get_proof(7)
+            .verify(&data[7 * 1024..8 * 1024], tree.root_hash()));
+
+        let uneven = MerkleTree::from_chunks(&data[..10_000], 1024).unwrap();
+        assert_eq!(uneven.leaf_count(), 10);
+        assert_eq!(uneven.leaves()[9].len(), 10_000 - 9 * 1024);
 
-        for (sibling_hash, position) in proof.0.into_iter() {
-            let combined = if position == LeftOrRight::Left {
-                [sibling_hash.as_slice(), hash.as_slice()].concat()
-            } else {
-                [hash.as_slice(), sibling_hash.as_slice()].concat()
-            };
-            hash = keccak(&combined);
+        let small = MerkleTree::from_chunks(&data[..100], 1024).unwrap();
+        assert_eq!(small.leaf_count(), 1);
+        assert_eq!(small.root_hash(), Keccak256::digest(&data[..100]));
+
+        assert_eq!(
+            MerkleTree::from_chunks(&[], 1024).err(),
+            Some(MerkleError::EmptyTree)
+        );
+    }
+
+    #[test]
+    fn diff_finds_changed_leaves() {
+        let leaves: Vec<String> = (0..16).map(|i| i.to_string()).collect();
+        let old = MerkleTree::new(leaves.clone());
+        let mut new = old.clone();
+        for index in [2, 3, 11] {
+            new.update_leaf(index, "changed".to_string()).unwrap();
         }
+        assert_eq!(old.diff(&new), Ok(vec![2, 3, 11]));
+        assert_eq!(new.diff(&old), Ok(vec![2, 3, 11]));
+        assert_eq!(old.diff(&old), Ok(vec![]));
 
-        hash == self.root_hash()
+        let odd = MerkleTree::new(leaves[..5].to_vec());
+        let mut changed = odd.clone();
+        changed.update_leaf(4, "changed".to_string()).unwrap();
+        assert_eq!(odd.diff(&changed), Ok(vec![4]));
+        assert_eq!(
+            old.diff(&odd),
+            Err(MerkleError::LeafCountMismatch { left: 16, right: 5 })
+        );
     }
-}
 
-pub fn build(leaves: Vec<String>) -> Vec<Vec<[u8; 32]>> {
-    let mut hashes = vec![];
-    let leaf_hashes: Vec<[u8; 32]> = leaves.iter().map(|leaf| keccak(leaf.as_bytes())).collect();
-    let mut branch_nodes = leaf_hashes.clone();
-    hashes.push(leaf_hashes);
+    #[test]
+    fn incremental_hashing_matches_concat() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        assert_eq!(
+            MerkleTree::new(leaves.clone()).root_hex(),
+            "d6234202ab47d68aded078f047ba81d3832024c0b39dd14b62ce878d04483291"
+        );
 
-    // Pair up leaf hashes and hash them together to make the next level of the tree
-    while branch_nodes.len() > 1 {
-        let mut new_branch_nodes = vec![];
-        let chunks = branch_nodes.chunks_exact(2);
-        let remainder = chunks.remainder();
-        for chunk in chunks {
-            let combined = [chunk[0].as_slice(), chunk[1].as_slice()].concat();
-            let hash = keccak(&combined);
-            new_branch_nodes.push(hash);
+        let (a, b) = (hash_leaf(b"a"), hash_leaf(b"b"));
+        assert_eq!(hash_nodes(&a, &b), Keccak256::digest(&[a, b].concat()));
+        let parts: [&[u8]; 3] = [b"\x01", &a, &b];
+        assert_eq!(
+            Keccak256::digest_parts(&parts),
+            Keccak256::digest(&parts.concat())
+        );
+        assert_eq!(
+            <TruncatedKeccak256 as MerkleHasher<20>>::digest_parts(&parts),
+            <TruncatedKeccak256 as MerkleHasher<20>>::digest(&parts.concat())
+        );
+        #[cfg(feature = "sha2")]
+        {
+            assert_eq!(
+                Sha256::digest_parts(&parts),
+                Sha256::digest(&parts.concat())
+            );
+            assert_eq!(
+                DoubleSha256::digest_parts(&parts),
+                DoubleSha256::digest(&parts.concat())
+            );
         }
-        if remainder.len() == 1 {
-            let combined = [remainder[0].as_slice(), remainder[0].as_slice()].concat();
-            let hash = keccak(&combined);
-            new_branch_nodes.push(hash);
+        #[cfg(feature = "blake3")]
+        assert_eq!(
+            Blake3::digest_parts(&parts),
+            Blake3::digest(&parts.concat())
+        );
+    }
+
+    #[test]
+    fn balanced_tree() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        assert_eq!(
+            MerkleTree::new_balanced(leaves[..4].to_vec()),
+            Ok(MerkleTree::new(leaves[..4].to_vec()))
+        );
+        assert_eq!(
+            MerkleTree::new_balanced(leaves),
+            Err(MerkleError::NotPowerOfTwo { len: 5 })
+        );
+        assert_eq!(
+            MerkleTree::<String>::new_balanced(vec![]),
+            Err(MerkleError::EmptyTree)
+        );
+    }
+
+    #[test]
+    fn height_for_matches_build() {
+        for (leaf_count, height) in [(0, 0), (1, 1), (2, 2), (4, 3), (5, 4), (8, 4), (9, 5)] {
+            assert_eq!(height_for(leaf_count), height);
+            let leaves: Vec<String> = (0..leaf_count).map(|i| i.to_string()).collect();
+            assert_eq!(build(&leaves).len(), height);
         }
-        hashes.push(new_branch_nodes.clone());
-        branch_nodes = new_branch_nodes;
+        assert_eq!(height_for(usize::MAX), usize::BITS as usize + 1);
     }
 
-    hashes.reverse();
-    hashes
-}
+    #[test]
+    fn map_leaves() {
+        let tree = MerkleTree::new_tagged(vec![" a", "B ", "c"], 0x00, 0x01);
+        assert_eq!(tree.map_leaves(|leaf| *leaf), tree);
 
-impl std::fmt::Display for MerkleTree {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut tree_str = String::new();
+        let normalized = tree.map_leaves(|leaf| leaf.trim().to_lowercase());
+        assert_eq!(normalized.leaves(), ["a", "b", "c"]);
+        assert_eq!(
+            normalized,
+            MerkleTree::new_tagged(["a", "b", "c"].map(String::from).to_vec(), 0x00, 0x01)
+        );
+    }
 
-        // Print the leaves
-        tree_str.push_str("Leaves:\n");
-        for (i, leaf) in self.leaves.iter().enumerate() {
-            tree_str.push_str(&format!("  {}: {}\n", i, leaf));
+    #[test]
+    fn validate_proof_against_tree() {
+        let tree = MerkleTree::new((0..5).map(|i| i.to_string()).collect());
+        for index in 0..5 {
+            assert!(tree.validate_proof_against_tree(index, &tree.proof(index).unwrap()));
         }
 
-        // Print the hashes
-        for (level, hashes) in self.hashes.iter().enumerate().rev() {
-            if hashes.len() == 1 {
-                tree_str.push_str("Root Hash:\n");
-                tree_str.push_str(&format!("  {}\n", hex::encode(hashes[0])));
-            } else {
-                tree_str.push_str(&format!("Level {}:\n", level));
-                for hash in hashes {
-                    tree_str.push_str(&format!("  {}\n", hex::encode(hash)));
-                }
-            }
+        // Leaves 0 and 1 share every sibling but the first
+        let proof = tree.proof(0).unwrap();
+        assert!(!tree.validate_proof_against_tree(1, &proof));
+        assert!(!tree.validate_proof_against_tree(0, &proof[1..]));
+        assert!(!tree.validate_proof_against_tree(5, &proof));
+
+        let mut tampered = tree.proof(4).unwrap();
+        tampered[2][0] ^= 1;
+        assert!(!tree.validate_proof_against_tree(4, &tampered));
+    }
+
+    #[test]
+    fn root_address() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.root_address(), tree.root_hash()[12..]);
+        assert_ne!(
+            tree.root_address(),
+            GenericMerkleTree::<_, TruncatedKeccak256, 20>::new(vec!["a", "b", "c"]).root_hash()
+        );
+    }
+
+    #[test]
+    fn leaf_metadata() {
+        let claims: Vec<(String, u64)> = [("0xabc", 100), ("0xdef", 250), ("0x123", 5)]
+            .map(|(address, amount)| (address.to_string(), amount))
+            .to_vec();
+        let tree = MerkleTree::new(claims);
+        assert_eq!(
+            tree.root_hash(),
+            MerkleTree::new(vec!["0xabc", "0xdef", "0x123"]).root_hash()
+        );
+
+        let index = tree.index_of("0xdef").unwrap();
+        assert_eq!(tree.leaf_meta(index), Some(&250));
+        assert_eq!(tree.leaf_meta(3), None);
+        assert!(tree.prove(("0x123".to_string(), 0), tree.get_proof(2)));
+        assert!(tree.to_string().contains("  1: 0xdef\n"));
+    }
+
+    #[test]
+    fn rebuild_in_place() {
+        let leaves: Vec<String> = (0..9).map(|i| i.to_string()).collect();
+        let mut tree = MerkleTree::new(leaves[..6].to_vec());
+        let edited = tree.leaves_mut().unwrap();
+        edited[2] = "x".to_string();
+        edited.extend(leaves[6..].iter().cloned());
+        tree.rebuild_in_place().unwrap();
+
+        let mut expected = leaves.clone();
+        expected[2] = "x".to_string();
+        assert_eq!(tree, MerkleTree::new(expected));
+        assert!(tree.verify_self());
+
+        let mut frozen = MerkleTree::new(leaves);
+        frozen.freeze();
+        assert_eq!(frozen.leaves_mut().err(), Some(MerkleError::Frozen));
+        assert_eq!(frozen.rebuild_in_place(), Err(MerkleError::Frozen));
+    }
+
+    #[test]
+    fn stale_leaves() {
+        let leaves: Vec<String> = ["a", "b", "c", "d"].map(String::from).to_vec();
+        let mut tree = MerkleTree::new(leaves.clone());
+        tree.leaves_mut().unwrap().push("x".to_string());
+        assert!(tree.is_stale());
+        assert_eq!(tree.proof(0), None);
+        assert_eq!(tree.try_proof(0), Err(MerkleError::Stale));
+        assert_eq!(tree.diff(&tree.clone()), Err(MerkleError::Stale));
+        assert_eq!(tree.split_at(2).err(), Some(MerkleError::Stale));
+        assert!(!tree.verify_self());
+
+        // Changing the tree through the API rebuilds it first
+        tree.push("y".to_string()).unwrap();
+        assert!(!tree.is_stale());
+        let mut expected = leaves.clone();
+        expected.extend(["x", "y"].map(String::from));
+        assert_eq!(tree, MerkleTree::new(expected.clone()));
+        assert!(tree.verify_self());
+
+        tree.leaves_mut().unwrap()[0] = "z".to_string();
+        tree.update_leaf(1, "w".to_string()).unwrap();
+        expected[..2].clone_from_slice(&["z", "w"].map(String::from));
+        assert_eq!(tree, MerkleTree::new(expected.clone()));
+
+        tree.leaves_mut().unwrap().pop();
+        tree.prune();
+        let left = [leaves.clone(), leaves].concat();
+        let merged = MerkleTree::merge(MerkleTree::new(left.clone()), tree);
+        expected.pop();
+        let all = [left, expected].concat();
+        assert_eq!(merged, MerkleTree::new(all));
+    }
+
+    #[test]
+    fn emptied_leaves() {
+        let mut tree = MerkleTree::new(vec!["a".to_string(), "b".to_string()]);
+        tree.leaves_mut().unwrap().clear();
+        assert_eq!(tree.rebuild_in_place(), Err(MerkleError::EmptyTree));
+        assert!(tree.is_stale());
+        assert_eq!(tree.proof(0), None);
+        assert_eq!(tree.try_proof(0), Err(MerkleError::Stale));
+        assert_eq!(tree.push("c".to_string()), Err(MerkleError::EmptyTree));
+        assert_eq!(tree.pop(), Err(MerkleError::EmptyTree));
+
+        tree.leaves_mut().unwrap().push("c".to_string());
+        tree.rebuild_in_place().unwrap();
+        assert_eq!(tree, MerkleTree::new(vec!["c".to_string()]));
+    }
+
+    #[test]
+    #[should_panic(expected = "rebuild the tree first")]
+    fn stale_get_proof_panics() {
+        let mut tree = MerkleTree::new(vec!["a", "b", "c"]);
+        tree.leaves_mut().unwrap().clear();
+        tree.get_proof(0);
+    }
+
+    #[test]
+    fn huge_leaf_counts() {
+        assert_eq!(check_leaf_count(MAX_LEAVES), Ok(()));
+        assert_eq!(
+            check_leaf_count(MAX_LEAVES + 1),
+            Err(MerkleError::TooManyLeaves {
+                len: MAX_LEAVES + 1,
+                max: MAX_LEAVES
+            })
+        );
+        assert_eq!(Levels::<32>::node_count(MAX_LEAVES), Some(usize::MAX));
+        assert_eq!(Levels::<32>::node_count(MAX_LEAVES + 1), None);
+        assert_eq!(height_for(usize::MAX), usize::BITS as usize + 1);
+
+        // Verifiers take counts from the proof, so they must cope with any of them
+        let root = MerkleTree::new(vec!["a"]).root_hash();
+        let proof = Proof {
+            leaf_index: usize::MAX - 1,
+            leaf_count: usize::MAX,
+            siblings: vec![root; usize::BITS as usize],
+        };
+        assert!(!proof.verify("a", root));
+        let multiproof = MultiProof {
+            leaf_count: usize::MAX,
+            indices: vec![usize::MAX - 1],
+            hashes: vec![root; usize::BITS as usize],
+        };
+        assert!(!verify_multiproof(&multiproof, &["a"], root));
+        assert!(!verify_consistency(
+            root,
+            root,
+            &[root; 2],
+            usize::MAX - 1,
+            usize::MAX
+        ));
+    }
+
+    #[test]
+    fn trees_in_hash_sets() {
+        let mut trees = std::collections::HashSet::new();
+        assert!(trees.insert(MerkleTree::new(vec!["a", "b", "c"])));
+        assert!(!trees.insert(MerkleTree::new(vec!["a", "b", "c"])));
+        assert!(trees.insert(MerkleTree::new(vec!["a", "c", "b"])));
+
+        let mut pushed = MerkleTree::new(vec!["a", "b"]);
+        pushed.push("c").unwrap();
+        assert!(trees.contains(&pushed));
+        assert_eq!(trees.len(), 2);
+    }
+
+    #[test]
+    fn streaming_verification() {
+        let tree = MerkleTree::new((0..13).map(|i| i.to_string()).collect());
+        let root = tree.root_hash();
+        for index in 0..13 {
+            let pairs = tree.get_proof(index).to_pairs();
+            assert!(verify_proof_streaming(
+                &index.to_string(),
+                pairs.iter().copied(),
+                root
+            ));
+            assert!(!verify_proof_streaming("x", pairs.into_iter(), root));
         }
 
-        write!(f, "{}", tree_str)
+        let endless = core::iter::repeat((false, root));
+        assert!(!verify_proof_streaming("0", endless, root));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn bounded_tree() {
+        let leaves: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        assert_eq!(
+            MerkleTree::new_bounded(leaves.clone(), 8),
+            Ok(MerkleTree::new(leaves.clone()))
+        );
+        assert_eq!(
+            MerkleTree::new_bounded(leaves, 7),
+            Err(MerkleError::TooManyLeaves { len: 8, max: 7 })
+        );
+    }
 
     #[test]
-    fn even_leaf_tree() {
-        let leaves = vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-        ];
-        let tree = MerkleTree::new(leaves);
-        println!("{}", tree);
-        assert_eq!(tree.hashes.len(), 3);
-        assert_eq!(tree.hashes[0].len(), 1);
-        assert_eq!(tree.hashes[1].len(), 2);
-        assert_eq!(tree.hashes[2].len(), 4);
+    fn split_at() {
+        let leaves: Vec<String> = (0..11).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(leaves.clone());
+        for index in [1, 4, 5, 10] {
+            let (left, right) = tree.split_at(index).unwrap();
+            assert_eq!(left, MerkleTree::new(leaves[..index].to_vec()));
+            assert_eq!(right, MerkleTree::new(leaves[index..].to_vec()));
+        }
+
+        let sorted = MerkleTree::new_sorted(leaves.clone());
+        let (left, _) = sorted.split_at(3).unwrap();
+        assert_eq!(left, MerkleTree::new_sorted(leaves[..3].to_vec()));
+
+        assert_eq!(tree.split_at(0), Err(MerkleError::EmptyTree));
+        assert_eq!(tree.split_at(11), Err(MerkleError::EmptyTree));
+        assert_eq!(
+            tree.split_at(12),
+            Err(MerkleError::IndexOutOfBounds { index: 12, len: 11 })
+        );
     }
 
     #[test]
-    fn odd_leaf_tree() {
-        let leaves = vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-            "e".to_string(),
-        ];
-        let tree = MerkleTree::new(leaves);
-        println!("{}", tree);
-        assert_eq!(tree.hashes.len(), 4);
-        assert_eq!(tree.hashes[0].len(), 1);
-        assert_eq!(tree.hashes[1].len(), 2);
-        assert_eq!(tree.hashes[2].len(), 3);
-        assert_eq!(tree.hashes[3].len(), 5);
+    fn pair_order() {
+        let leaves = vec!["a", "b", "c"];
+        assert_eq!(
+            MerkleTree::new(leaves.clone()).pair_order(),
+            PairOrder::Positional
+        );
+        assert_eq!(
+            MerkleTree::new_tagged(leaves.clone(), 0x00, 0x01).pair_order(),
+            PairOrder::Positional
+        );
+        let sorted = MerkleTree::new_sorted(leaves);
+        assert_eq!(sorted.pair_order(), PairOrder::Sorted);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&sorted).unwrap();
+            let restored: MerkleTree<String> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.pair_order(), PairOrder::Sorted);
+        }
     }
 
     #[test]
-    fn test_different_value() {
-        let tree1 = MerkleTree::new(vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-            "e".to_string(),
-        ]);
-        let tree2 = MerkleTree::new(vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-            "f".to_string(),
-        ]);
-        let tree3 = MerkleTree::new(vec![
-            "b".to_string(),
-            "a".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-            "e".to_string(),
-        ]);
-        assert_ne!(tree1.root_hash(), tree2.root_hash());
-        assert_ne!(tree1.root_hash(), tree3.root_hash());
-        assert_ne!(tree2.root_hash(), tree3.root_hash());
+    fn keccak512_tree() {
+        let tree = MerkleTree512::new(vec!["a", "b", "c"]);
+        assert_eq!(tree.algo(), HashAlgo::Keccak512);
+        let root: [u8; 64] = tree.root_hash();
+        assert_eq!(
+            hex::encode(tree.leaf_hash(0).unwrap()),
+            "9c46dbec5d03f74352cc4a4da354b4e9796887eeb66ac292617692e765dbe400\
+             352559b16229f97b27614b51dbfbbb14613f2c10350435a8feaf53f73ba01c7c"
+        );
+        let (a, b) = (tree.leaf_hash(0).unwrap(), tree.leaf_hash(1).unwrap());
+        assert_eq!(
+            tree.get_node(1, 0),
+            Some(Keccak512::digest(&[a, b].concat()))
+        );
+        assert_ne!(root[..32], MerkleTree::new(vec!["a", "b", "c"]).root_hash());
+
+        assert!(tree.prove("c", tree.get_proof(2)));
+        assert!(!tree.prove("a", tree.get_proof(2)));
     }
 
     #[test]
-    fn proof_siblings() {
-        let leaves = vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-        ];
-        let tree = MerkleTree::new(leaves);
-        println!("{}", tree);
+    fn prune_and_rehydrate() {
+        let leaves: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let full = MerkleTree::new(leaves.clone());
+        let mut tree = full.clone();
+        tree.prune();
+        assert!(tree.is_pruned());
 
-        // Get a proof for "b" (index 1)
-        let proof = tree.get_proof(1);
-        println!(
-            "{:?}",
-            proof
-                .0
-                .iter()
-                .map(|x| (hex::encode(x.0), x.1))
-                .collect::<Vec<(String, LeftOrRight)>>()
+        assert_eq!(tree.root_hash(), full.root_hash());
+        assert_eq!(tree.depth(), full.depth());
+        assert_eq!(tree.leaf_hash(4), full.leaf_hash(4));
+        assert_eq!(tree.index_of("3"), Some(3));
+        assert_eq!(tree.get_node(1, 0), None);
+        assert_eq!(tree.width_at(1), full.width_at(1));
+        assert_eq!(tree.levels().count(), 2);
+        assert!(tree.verify_self());
+        assert_eq!(tree, full);
+
+        assert_eq!(tree.proof(1), None);
+        assert_eq!(tree.try_proof(1), Err(MerkleError::Pruned));
+        assert_eq!(tree.diff(&full), Err(MerkleError::Pruned));
+        assert_eq!(tree.consistency_proof(3, 5), full.consistency_proof(3, 5));
+        assert_eq!(tree.to_bytes(), full.to_bytes());
+
+        tree.rehydrate();
+        assert!(!tree.is_pruned());
+        assert_eq!(tree.try_proof(1), Ok(full.proof(1).unwrap()));
+        assert_eq!(
+            tree.try_proof(5),
+            Err(MerkleError::IndexOutOfBounds { index: 5, len: 5 })
         );
-        // assert_eq!(proof.len(), 2);
-        // assert_eq!(proof[0], tree.hashes[2][1]);
-        // assert_eq!(proof[1], tree.hashes[2][2]);
+        assert_eq!(format!("{:?}", tree), format!("{:?}", full));
+
+        // Changing a pruned tree rehydrates it first
+        tree.prune();
+        tree.push("5".to_string()).unwrap();
+        assert!(!tree.is_pruned());
+        assert_eq!(
+            tree,
+            MerkleTree::new((0..6).map(|i| i.to_string()).collect())
+        );
+
+        // A single leaf is already its own root, so there's nothing to drop
+        let mut single = MerkleTree::new(vec!["a"]);
+        single.prune();
+        assert!(!single.is_pruned());
+        assert_eq!(single.proof(0), Some(vec![]));
+
+        #[cfg(feature = "serde")]
+        {
+            let mut pruned = full.clone();
+            pruned.prune();
+            let json = serde_json::to_string(&pruned).unwrap();
+            assert_eq!(json, serde_json::to_string(&full).unwrap());
+            let restored: MerkleTree<String> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored, full);
+        }
     }
 
     #[test]
-    fn valid_proof() {
-        let leaves = vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-        ];
-        let tree = MerkleTree::new(leaves);
-        println!("{}", tree);
+    #[should_panic(expected = "pruned")]
+    fn pruned_get_proof_panics() {
+        let mut tree = MerkleTree::new(vec!["a", "b", "c"]);
+        tree.prune();
+        tree.get_proof(0);
+    }
 
-        // Get a proof for "b" (index 1)
-        let proof = tree.get_proof(1);
-        assert!(tree.prove("b".to_string(), proof));
+    #[test]
+    fn entries() {
+        let mut tree = MerkleTree::new(vec!["a", "b", "c"]);
+        tree.prune();
+        let entries: Vec<_> = tree.entries().collect();
+        assert_eq!(entries.len(), 3);
+        for (index, leaf, hash) in entries {
+            assert_eq!(*leaf, tree.leaves()[index]);
+            assert_eq!(hash, hash_leaf(leaf.as_bytes()));
+            assert_eq!(Some(hash), tree.leaf_hash(index));
+        }
     }
 
     #[test]
-    fn invalid_proof_wrong_element() {
-        let leaves = vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-        ];
-        let tree = MerkleTree::new(leaves);
-        println!("{}", tree);
+    fn verify_proof_sorted() {
+        // The sorted-pair root pinned in `golden`, computed outside the crate
+        let root: [u8; 32] =
+            hex::decode("05ad7afe50ab2b3c46ab3b1f042a5ce67c748dd52b81faf469bacf98912edbbe")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let leaves = ["e", "d", "c", "b", "a"];
+        let tree = MerkleTree::new_sorted(leaves.to_vec());
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.proof(index).unwrap();
+            assert!(super::verify_proof_sorted(*leaf, &proof, root));
+            assert!(!super::verify_proof_sorted("f", &proof, root));
+        }
 
-        // Get a proof for "b" (index 1)
-        let proof = tree.get_proof(1);
-        assert!(tree.prove("a".to_string(), proof));
+        // Positional trees order their pairs differently
+        let positional = MerkleTree::new(leaves.to_vec());
+        assert!(!super::verify_proof_sorted(
+            "e",
+            &positional.proof(0).unwrap(),
+            positional.root_hash()
+        ));
+        assert!(!super::verify_proof_sorted(
+            "a",
+            &vec![[0u8; 32]; MAX_PROOF_LEN + 1],
+            root
+        ));
     }
 
     #[test]
-    fn invalid_proof_wrong_sibling() {
-        let leaves = vec![
-            "a".to_string(),
-            "b".to_string(),
-            "c".to_string(),
-            "d".to_string(),
-        ];
-        let tree = MerkleTree::new(leaves);
-        println!("{}", tree);
+    fn num_nodes() {
+        assert_eq!(MerkleTree::new(vec!["a"]).num_nodes(), 1);
+        assert_eq!(MerkleTree::new(vec!["a", "b", "c", "d"]).num_nodes(), 7);
+        let mut tree = MerkleTree::new(vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(tree.num_nodes(), 5 + 3 + 2 + 1);
+        tree.prune();
+        assert_eq!(tree.num_nodes(), 6);
+    }
 
-        // Get a proof for "b" (index 1)
-        let mut proof = tree.get_proof(1);
-        proof.0[0].0 = [0u8; 32];
-        assert!(tree.prove("b".to_string(), proof));
+    #[test]
+    fn ct_eq_root() {
+        let tree = MerkleTree::new(vec!["a", "b", "c"]);
+        let root = tree.root_hash();
+        assert!(tree.ct_eq_root(&root));
+        for index in [0, 15, 31] {
+            let mut other = root;
+            other[index] ^= 0x80;
+            assert!(!tree.ct_eq_root(&other));
+        }
     }
 }
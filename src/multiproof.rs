@@ -0,0 +1,270 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{GenericMerkleTree, Hashable, Keccak256, MerkleHasher, Scheme};
+
+/// A proof that several leaves are all included in the same tree.
+///
+/// Sibling hashes shared between the leaves' paths, or computable from the leaves themselves,
+/// are only included once, so this is smaller than one [`crate::Proof`] per leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof<const N: usize = 32> {
+    /// The number of leaves in the tree the proof was generated from.
+    pub leaf_count: usize,
+    /// The proven leaf indices, sorted and without duplicates.
+    pub indices: Vec<usize>,
+    /// The sibling hashes the verifier can't compute itself, level by level from the leaves
+    /// upward and left to right within each level.
+    pub hashes: Vec<[u8; N]>,
+}
+
+impl<T: Hashable, H: MerkleHasher<N>, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Builds a [`MultiProof`] for the leaves at `indices`, which may be in any order and may
+    /// repeat.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is out of bounds or the tree is pruned.
+    pub fn multiproof(&self, indices: &[usize]) -> MultiProof<N> {
+        self.assert_complete();
+        let mut known = indices.to_vec();
+        known.sort_unstable();
+        known.dedup();
+        if let Some(&index) = known.last() {
+            assert!(
+                index < self.leaves.len(),
+                "leaf index {} out of bounds",
+                index
+            );
+        }
+        let proven = known.clone();
+
+        let mut hashes = vec![];
+        for level in self.hashes.iter().skip(1).rev() {
+            let mut parents = vec![];
+            let mut i = 0;
+            while i < known.len() {
+                let index = known[i];
+                if index % 2 == 1 {
+                    hashes.push(level[index - 1]);
+                } else if known.get(i + 1) == Some(&(index + 1)) {
+                    // Both children are known, so the verifier can hash them itself
+                    i += 1;
+                } else if index + 1 < level.len() {
+                    hashes.push(level[index + 1]);
+                }
+                parents.push(index / 2);
+                i += 1;
+            }
+            known = parents;
+        }
+
+        MultiProof {
+            leaf_count: self.leaves.len(),
+            indices: proven,
+            hashes,
+        }
+    }
+}
+
+/// A proof that a contiguous run of leaves, `start..end`, is included in a tree.
+///
+/// This is a [`MultiProof`] specialized to a range: only the sibling hashes along the two
+/// boundaries of the run are needed, and the indices don't have to be listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeProof<const N: usize = 32> {
+    /// The number of leaves in the tree the proof was generated from.
+    pub leaf_count: usize,
+    /// The index of the first proven leaf.
+    pub start: usize,
+    /// One past the index of the last proven leaf.
+    pub end: usize,
+    /// The boundary sibling hashes, in the same order as [`MultiProof::hashes`].
+    pub hashes: Vec<[u8; N]>,
+}
+
+impl<T: Hashable, H: MerkleHasher<N>, const N: usize> GenericMerkleTree<T, H, N> {
+    /// Builds a [`RangeProof`] for the leaves at `start..end`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty or extends past the last leaf.
+    pub fn range_proof(&self, start: usize, end: usize) -> RangeProof<N> {
+        assert!(start < end, "leaf range {}..{} is empty", start, end);
+        let indices: Vec<usize> = (start..end).collect();
+        let MultiProof {
+            leaf_count, hashes, ..
+        } = self.multiproof(&indices);
+        RangeProof {
+            leaf_count,
+            start,
+            end,
+            hashes,
+        }
+    }
+}
+
+/// Checks that `leaves` are exactly the leaves at `proof.start..proof.end` under `root`.
+///
+/// Returns `false` rather than panicking for any malformed proof.
+pub fn verify_range_proof<L: Hashable>(proof: &RangeProof, leaves: &[L], root: [u8; 32]) -> bool {
+    if proof.start >= proof.end {
+        return false;
+    }
+    let multiproof = MultiProof {
+        leaf_count: proof.leaf_count,
+        indices: (proof.start..proof.end).collect(),
+        hashes: proof.hashes.clone(),
+    };
+    fold_multiproof::<Keccak256, L, 32>(Scheme::default(), &multiproof, leaves) == Some(root)
+}
+
+/// Checks that `leaves`, located at `proof.indices`, are all included under `root`.
+///
+/// `leaves` must be in the same order as `proof.indices`. Returns `false` rather than
+/// panicking for any malformed proof.
+pub fn verify_multiproof<L: Hashable>(proof: &MultiProof, leaves: &[L], root: [u8; 32]) -> bool {
+    fold_multiproof::<Keccak256, L, 32>(Scheme::default(), proof, leaves) == Some(root)
+}
+
+fn fold_multiproof<H: MerkleHasher<N>, L: Hashable, const N: usize>(
+    scheme: Scheme,
+    proof: &MultiProof<N>,
+    leaves: &[L],
+) -> Option<[u8; N]> {
+    let indices = &proof.indices;
+    if indices.is_empty() || leaves.len() != indices.len() {
+        return None;
+    }
+    if indices.windows(2).any(|pair| pair[0] >= pair[1])
+        || indices[indices.len() - 1] >= proof.leaf_count
+    {
+        return None;
+    }
+
+    let mut known: Vec<(usize, [u8; N])> = indices
+        .iter()
+        .zip(leaves)
+        .map(|(index, leaf)| (*index, scheme.hash_leaf::<H, L, N>(leaf)))
+        .collect();
+    let mut siblings = proof.hashes.iter();
+    let mut width = proof.leaf_count;
+
+    // Mirror `multiproof`, consuming sibling hashes in the order it produced them
+    while width > 1 {
+        let mut parents = vec![];
+        let mut i = 0;
+        while i < known.len() {
+            let (index, hash) = known[i];
+            let parent = if index % 2 == 1 {
+                scheme.hash_nodes::<H, N>(siblings.next()?, &hash)
+            } else if known.get(i + 1).map(|(next, _)| *next) == Some(index + 1) {
+                i += 1;
+                scheme.hash_nodes::<H, N>(&hash, &known[i].1)
+            } else if index + 1 < width {
+                scheme.hash_nodes::<H, N>(&hash, siblings.next()?)
+            } else {
+                scheme.hash_nodes::<H, N>(&hash, &scheme.odd_sibling(&hash))
+            };
+            parents.push((index / 2, parent));
+            i += 1;
+        }
+        known = parents;
+        width = width.div_ceil(2);
+    }
+
+    if siblings.next().is_some() {
+        return None;
+    }
+    Some(known[0].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    fn leaves(n: usize) -> Vec<String> {
+        (0..n).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn reconstructs_root() {
+        let leaves = leaves(100);
+        let tree = MerkleTree::new(leaves.clone());
+
+        for indices in [vec![0], vec![99], vec![3, 4, 5], vec![98, 1, 50, 51, 1]] {
+            let proof = tree.multiproof(&indices);
+            let proven: Vec<&String> = proof.indices.iter().map(|i| &leaves[*i]).collect();
+            assert!(verify_multiproof(&proof, &proven, tree.root_hash()));
+        }
+    }
+
+    #[test]
+    fn shared_nodes_are_not_duplicated() {
+        let tree = MerkleTree::new(leaves(8));
+
+        // Siblings 0 and 1 share every ancestor, so only the two upper siblings are needed
+        assert_eq!(tree.multiproof(&[0, 1]).hashes.len(), 2);
+        assert_eq!(tree.multiproof(&[0, 7]).hashes.len(), 4);
+        assert!(tree
+            .multiproof(&(0..8).collect::<Vec<_>>())
+            .hashes
+            .is_empty());
+    }
+
+    #[test]
+    fn rejects_wrong_leaves() {
+        let leaves = leaves(9);
+        let tree = MerkleTree::new(leaves.clone());
+        let proof = tree.multiproof(&[2, 8]);
+
+        assert!(verify_multiproof(
+            &proof,
+            &[&leaves[2], &leaves[8]],
+            tree.root_hash()
+        ));
+        assert!(!verify_multiproof(
+            &proof,
+            &[&leaves[8], &leaves[2]],
+            tree.root_hash()
+        ));
+        assert!(!verify_multiproof(&proof, &[&leaves[2]], tree.root_hash()));
+
+        let mut truncated = proof.clone();
+        truncated.hashes.pop();
+        assert!(!verify_multiproof(
+            &truncated,
+            &[&leaves[2], &leaves[8]],
+            tree.root_hash()
+        ));
+    }
+
+    #[test]
+    fn range_proof() {
+        let leaves = leaves(8);
+        let tree = MerkleTree::new(leaves.clone());
+        let proof = tree.range_proof(2, 5);
+
+        assert!(verify_range_proof(&proof, &leaves[2..5], tree.root_hash()));
+        assert!(!verify_range_proof(&proof, &leaves[3..6], tree.root_hash()));
+        assert!(!verify_range_proof(&proof, &leaves[2..4], tree.root_hash()));
+        // Leaf 5, the parent of leaves 0 and 1, and the parent of leaves 6 and 7
+        assert_eq!(proof.hashes.len(), 3);
+    }
+
+    #[test]
+    fn range_proof_through_odd_nodes() {
+        let leaves = leaves(11);
+        let tree = MerkleTree::new(leaves.clone());
+
+        for (start, end) in [(8, 11), (10, 11), (0, 11), (5, 9)] {
+            let proof = tree.range_proof(start, end);
+            assert!(verify_range_proof(
+                &proof,
+                &leaves[start..end],
+                tree.root_hash()
+            ));
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use rayon::prelude::*;
+
+use crate::{Hashable, Keccak256, MerkleHasher, Scheme};
+
+/// Like [`crate::build`], but hashes leaves and each level's pairs across rayon's thread pool.
+///
+/// Produces exactly the same levels as `build`.
+pub fn build_parallel<T: Hashable + Sync>(leaves: Vec<T>) -> Vec<Vec<[u8; 32]>> {
+    build_levels_parallel::<Keccak256, T, 32>(&leaves, Scheme::default())
+}
+
+fn build_levels_parallel<H: MerkleHasher<N>, T: Hashable + Sync, const N: usize>(
+    leaves: &[T],
+    scheme: Scheme,
+) -> Vec<Vec<[u8; N]>> {
+    if leaves.is_empty() {
+        return vec![];
+    }
+
+    let mut hashes = vec![];
+    let mut branch_nodes: Vec<[u8; N]> = leaves
+        .par_iter()
+        .map(|leaf| scheme.hash_leaf::<H, T, N>(leaf))
+        .collect();
+
+    while branch_nodes.len() > 1 {
+        // A trailing chunk of one is the odd node
+        let new_branch_nodes = branch_nodes
+            .par_chunks(2)
+            .map(|pair| match pair {
+                [left, right] => scheme.hash_nodes::<H, N>(left, right),
+                _ => scheme.hash_nodes::<H, N>(&pair[0], &scheme.odd_sibling(&pair[0])),
+            })
+            .collect();
+        hashes.push(branch_nodes);
+        branch_nodes = new_branch_nodes;
+    }
+    hashes.push(branch_nodes);
+
+    hashes.reverse();
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build;
+
+    #[test]
+    fn matches_sequential_build() {
+        let leaves: Vec<String> = (0..10_000).map(|i| i.to_string()).collect();
+        assert_eq!(build_parallel(leaves.clone()), build(&leaves));
+    }
+
+    #[test]
+    fn odd_and_empty_inputs() {
+        let leaves: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        assert_eq!(build_parallel(leaves.clone()), build(&leaves));
+        assert!(build_parallel::<String>(vec![]).is_empty());
+    }
+}
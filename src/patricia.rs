@@ -0,0 +1,271 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Keccak256, MerkleHasher};
+
+/// A hexary Merkle-Patricia trie, the structure behind Ethereum's state, storage, transaction
+/// and receipt roots, kept separate from the binary [`crate::MerkleTree`].
+///
+/// Keys are split into 4-bit nibbles, high nibble first, and each node branches 16 ways on the
+/// next nibble. Runs of nibbles that no two keys diverge on are collapsed into extension and
+/// leaf nodes. Each node is RLP-encoded, and a parent refers to a child by the Keccak-256 hash
+/// of its encoding, or by the encoding itself if that's shorter than 32 bytes. The root is the
+/// hash of the root node's encoding, even when it's short, so a trie with the same entries has
+/// the same root as Ethereum's.
+///
+/// Keys are used exactly as given: Ethereum's "secure" tries, such as the state trie, hash
+/// each key with Keccak-256 before inserting it, and RLP-encode account and storage values.
+/// Only the entries are stored, and [`PatriciaTrie::root`] hashes the trie from them on each
+/// call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatriciaTrie {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PatriciaTrie {
+    pub fn new() -> Self {
+        PatriciaTrie {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the value of `key`, replacing any earlier value.
+    ///
+    /// As in Ethereum, a trie can't hold an empty value: inserting one removes `key`.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        if value.is_empty() {
+            self.entries.remove(key);
+        } else {
+            self.entries.insert(key.to_vec(), value.to_vec());
+        }
+    }
+
+    /// The value of `key`, or `None` if it was never inserted.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    /// The root hash, which for an empty trie is the hash of the empty string's RLP encoding,
+    /// `56e81f…b421`.
+    pub fn root(&self) -> [u8; 32] {
+        // Sorting the keys by byte also sorts them by nibble, so every subtree is a contiguous
+        // run of entries
+        let entries: Vec<(Vec<u8>, &[u8])> = self
+            .entries
+            .iter()
+            .map(|(key, value)| (nibbles(key), value.as_slice()))
+            .collect();
+        let root = match entries.is_empty() {
+            true => encode_bytes(&[]),
+            false => encode_node(&entries, 0),
+        };
+        Keccak256::digest(&root)
+    }
+
+    /// The number of keys with a value.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// The RLP encoding of the node over `entries`, sorted and non-empty, whose keys all share
+// their first `depth` nibbles
+fn encode_node(entries: &[(Vec<u8>, &[u8])], depth: usize) -> Vec<u8> {
+    if let [(key, value)] = entries {
+        return encode_list(&[
+            encode_bytes(&hex_prefix(&key[depth..], true)),
+            encode_bytes(value),
+        ]);
+    }
+
+    // In sorted order, the first and last keys share the fewest nibbles of any pair
+    let (first, last) = (&entries[0].0, &entries[entries.len() - 1].0);
+    let shared = first[depth..]
+        .iter()
+        .zip(&last[depth..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let child = encode_node(entries, depth + shared);
+        return encode_list(&[
+            encode_bytes(&hex_prefix(&first[depth..depth + shared], false)),
+            reference(child),
+        ]);
+    }
+
+    // A key that ends here sorts first, and its value goes in the branch's 17th slot
+    let (value, rest) = match entries[0].0.len() == depth {
+        true => (entries[0].1, &entries[1..]),
+        false => (&[][..], entries),
+    };
+    let mut items = Vec::with_capacity(17);
+    let mut start = 0;
+    for nibble in 0..16 {
+        let end = start + rest[start..].partition_point(|(key, _)| key[depth] == nibble);
+        items.push(match start == end {
+            true => encode_bytes(&[]),
+            false => reference(encode_node(&rest[start..end], depth + 1)),
+        });
+        start = end;
+    }
+    items.push(encode_bytes(value));
+    encode_list(&items)
+}
+
+// How a parent refers to a child: inline if its encoding is short, by hash otherwise
+fn reference(encoded: Vec<u8>) -> Vec<u8> {
+    match encoded.len() < 32 {
+        true => encoded,
+        false => encode_bytes(&Keccak256::digest(&encoded)),
+    }
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+// Packs nibbles into bytes behind a flag nibble recording whether the path ends at a leaf and
+// whether it has an odd number of nibbles, in which case the first shares the flag's byte
+fn hex_prefix(path: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + path.len() as u8 % 2;
+    let (mut packed, rest) = match path.split_first() {
+        Some((first, rest)) if flag & 1 == 1 => (vec![flag << 4 | first], rest),
+        _ => (vec![flag << 4], path),
+    };
+    packed.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    packed
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if let [byte @ 0..=0x7f] = bytes {
+        return vec![*byte];
+    }
+    let mut encoded = length_prefix(bytes.len(), 0x80);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+// Concatenates items that are already encoded
+fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut encoded = length_prefix(items.iter().map(Vec::len).sum(), 0xc0);
+    items
+        .iter()
+        .for_each(|item| encoded.extend_from_slice(item));
+    encoded
+}
+
+// A short payload's length is added to `offset`; a longer one's is written out in big-endian
+// bytes, whose count is added to `offset + 55`
+fn length_prefix(len: usize, offset: u8) -> Vec<u8> {
+    if len <= 55 {
+        return vec![offset + len as u8];
+    }
+    let bytes = len.to_be_bytes();
+    let significant = &bytes[bytes.iter().take_while(|byte| **byte == 0).count()..];
+    let mut prefix = vec![offset + 55 + significant.len() as u8];
+    prefix.extend_from_slice(significant);
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root_of(entries: &[(&str, &str)]) -> String {
+        let mut trie = PatriciaTrie::new();
+        for (key, value) in entries {
+            trie.insert(key.as_bytes(), value.as_bytes());
+        }
+        hex::encode(trie.root())
+    }
+
+    // Roots from the `trietest.json` and `trieanyorder.json` fixtures in ethereum/tests
+    #[test]
+    fn ethereum_roots() {
+        assert_eq!(
+            root_of(&[]),
+            "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+        );
+        assert_eq!(
+            root_of(&[("A", &"a".repeat(50))]),
+            "d23786fb4a010da3ce639d66d5e904a11dbc02746d1ce25029e53290cabf28ab"
+        );
+        assert_eq!(
+            root_of(&[
+                ("doe", "reindeer"),
+                ("dog", "puppy"),
+                ("dogglesworth", "cat")
+            ]),
+            "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+        );
+        assert_eq!(
+            root_of(&[
+                ("do", "verb"),
+                ("horse", "stallion"),
+                ("doge", "coin"),
+                ("dog", "puppy"),
+            ]),
+            "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+        );
+        assert_eq!(
+            root_of(&[("foo", "bar"), ("food", "bass")]),
+            "17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3"
+        );
+        assert_eq!(
+            root_of(&[("be", "e"), ("dog", "puppy"), ("bed", "d")]),
+            "3f67c7a47520f79faa29255d2d3c084a7a6df0453116ed7232ff10277a8be68b"
+        );
+        assert_eq!(
+            root_of(&[("test", "test"), ("te", "testy")]),
+            "8452568af70d8d140f58d941338542f645fcca50094b20f3c3d8c3df49337928"
+        );
+    }
+
+    #[test]
+    fn insert_order_and_removal() {
+        let entries = [
+            ("doe", "reindeer"),
+            ("dog", "puppy"),
+            ("dogglesworth", "cat"),
+        ];
+        let mut reversed = entries;
+        reversed.reverse();
+        assert_eq!(root_of(&entries), root_of(&reversed));
+
+        let mut trie = PatriciaTrie::new();
+        trie.insert(b"dog", b"puppy");
+        let root = trie.root();
+        trie.insert(b"doge", b"coin");
+        trie.insert(b"dog", b"wolf");
+        assert_eq!(trie.get(b"dog"), Some(&b"wolf"[..]));
+        assert_eq!(trie.len(), 2);
+
+        trie.insert(b"doge", b"");
+        trie.insert(b"dog", b"puppy");
+        assert_eq!(trie.get(b"doge"), None);
+        assert_eq!(trie.root(), root);
+    }
+
+    #[test]
+    fn rlp() {
+        assert_eq!(encode_bytes(b"dog"), b"\x83dog");
+        assert_eq!(encode_bytes(&[0x0f]), [0x0f]);
+        assert_eq!(encode_bytes(&[]), [0x80]);
+        let long = encode_bytes(&[b'a'; 56]);
+        assert_eq!(long[..2], [0xb8, 56]);
+        assert_eq!(
+            encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]),
+            b"\xc8\x83cat\x83dog"
+        );
+        assert_eq!(hex_prefix(&[1, 2, 3], false), [0x11, 0x23]);
+        assert_eq!(hex_prefix(&[0, 1, 2], true), [0x30, 0x12]);
+        assert_eq!(hex_prefix(&[1, 2], true), [0x20, 0x12]);
+    }
+}
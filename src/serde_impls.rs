@@ -0,0 +1,101 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{build_levels, GenericMerkleTree, Hashable, MerkleError, MerkleHasher, Scheme};
+
+// Hashes are stored as lowercase hex strings so that serialized trees stay readable
+#[derive(Serialize)]
+struct SerializedTree<'a, T> {
+    leaves: &'a [T],
+    hashes: Vec<Vec<String>>,
+    scheme: Scheme,
+}
+
+#[derive(Deserialize)]
+struct DeserializedTree<T> {
+    leaves: Vec<T>,
+    hashes: Vec<Vec<String>>,
+    #[serde(default)]
+    scheme: Scheme,
+}
+
+/// A pruned tree is serialized in full, rehashing the levels it dropped, like
+/// [`GenericMerkleTree::to_bytes`].
+impl<T, H, const N: usize> Serialize for GenericMerkleTree<T, H, N>
+where
+    T: Hashable + Serialize,
+    H: MerkleHasher<N>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedTree {
+            leaves: &self.leaves,
+            hashes: self
+                .full_levels()
+                .iter()
+                .map(|level| level.iter().map(hex::encode).collect())
+                .collect(),
+            scheme: self.scheme,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializing rebuilds the tree from its leaves and rejects the input if the stored hashes
+/// don't match, so a corrupted cache can't produce a tree with the wrong root.
+impl<'de, T, H, const N: usize> Deserialize<'de> for GenericMerkleTree<T, H, N>
+where
+    T: Hashable + Deserialize<'de>,
+    H: MerkleHasher<N>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tree = DeserializedTree::<T>::deserialize(deserializer)?;
+
+        let mut hashes = Vec::with_capacity(tree.hashes.len());
+        for level in &tree.hashes {
+            let mut decoded = Vec::with_capacity(level.len());
+            for hash in level {
+                let mut bytes = [0u8; N];
+                hex::decode_to_slice(hash, &mut bytes).map_err(D::Error::custom)?;
+                decoded.push(bytes);
+            }
+            hashes.push(decoded);
+        }
+
+        let levels = build_levels::<H, T, N>(&tree.leaves, tree.scheme);
+        if levels != hashes {
+            return Err(D::Error::custom(MerkleError::InconsistentTree));
+        }
+
+        Ok(GenericMerkleTree::from_parts(
+            tree.leaves,
+            levels,
+            tree.scheme,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MerkleTree;
+
+    #[test]
+    fn round_trip() {
+        let tree = MerkleTree::new_sorted(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        let json = serde_json::to_string(&tree).unwrap();
+        let loaded: MerkleTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.root_hash(), tree.root_hash());
+        assert_eq!(loaded.hashes, tree.hashes);
+        assert!(loaded.prove("b".to_string(), loaded.get_proof(1)));
+    }
+
+    #[test]
+    fn rejects_corrupted_hashes() {
+        let tree = MerkleTree::new(vec!["a".to_string(), "b".to_string()]);
+        let json = serde_json::to_string(&tree).unwrap();
+        let corrupted = json.replace(&hex::encode(tree.root_hash()), &hex::encode([0u8; 32]));
+        assert!(serde_json::from_str::<MerkleTree>(&corrupted).is_err());
+    }
+}
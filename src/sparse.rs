@@ -0,0 +1,267 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::{hash_nodes, Keccak256, MerkleHasher};
+
+// Every key is hashed to a 256-bit path from the root, one bit per level, most significant
+// bit first
+const DEPTH: usize = 256;
+
+/// A Keccak-256 Merkle tree with a leaf for every possible 256-bit key, almost all of them
+/// empty.
+///
+/// A key's leaf sits at the path given by the Keccak-256 hash of the key, and holds the
+/// Keccak-256 hash of its value, or all zeroes if the key hasn't been inserted. A parent is
+/// `keccak(left || right)`, as in [`crate::MerkleTree`]. Only the nodes on the paths to
+/// inserted keys are stored; every other subtree is empty, and its root depends only on its
+/// height.
+///
+/// Proofs from [`SparseMerkleTree::proof`] show either that a key holds a value or that it
+/// was never inserted, see [`verify_sparse_proof`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMerkleTree {
+    // Non-empty nodes by depth from the root and path prefix, with the bits past the depth
+    // cleared
+    nodes: BTreeMap<(usize, [u8; 32]), [u8; 32]>,
+    // `empty[height]` is the root of an empty subtree `height` levels above the leaves
+    empty: Vec<[u8; 32]>,
+    len: usize,
+}
+
+/// A proof that a key does or doesn't hold a value in a [`SparseMerkleTree`].
+///
+/// Siblings that are empty subtrees are left out, as the verifier can compute them itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseProof {
+    /// Bit `height % 8` of byte `height / 8` is set when the sibling `height` levels above the
+    /// leaves is included in `siblings`.
+    pub bitmap: [u8; 32],
+    /// The non-empty siblings, ordered from the leaf level upward.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl SparseMerkleTree {
+    pub fn new() -> Self {
+        let mut empty = Vec::with_capacity(DEPTH + 1);
+        empty.push([0u8; 32]);
+        for height in 0..DEPTH {
+            empty.push(hash_nodes(&empty[height], &empty[height]));
+        }
+        SparseMerkleTree {
+            nodes: BTreeMap::new(),
+            empty,
+            len: 0,
+        }
+    }
+
+    /// Sets the value of `key`, replacing any earlier value, and updates the root.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        let path = Keccak256::digest(key);
+        let mut node = Keccak256::digest(value);
+        if self.nodes.insert((DEPTH, path), node).is_none() {
+            self.len += 1;
+        }
+
+        for depth in (0..DEPTH).rev() {
+            let sibling = self.node(depth + 1, flip_bit(prefix(&path, depth + 1), depth));
+            node = if bit(&path, depth) {
+                hash_nodes(&sibling, &node)
+            } else {
+                hash_nodes(&node, &sibling)
+            };
+            self.nodes.insert((depth, prefix(&path, depth)), node);
+        }
+    }
+
+    /// The root over every key, which is the root of an entirely empty tree if nothing has
+    /// been inserted.
+    pub fn root(&self) -> [u8; 32] {
+        self.node(0, [0u8; 32])
+    }
+
+    /// Proves the value of `key`, or that it has none, for [`verify_sparse_proof`].
+    pub fn proof(&self, key: &[u8]) -> SparseProof {
+        let path = Keccak256::digest(key);
+        let mut proof = SparseProof {
+            bitmap: [0u8; 32],
+            siblings: Vec::new(),
+        };
+        for depth in (1..=DEPTH).rev() {
+            let sibling = flip_bit(prefix(&path, depth), depth - 1);
+            if let Some(hash) = self.nodes.get(&(depth, sibling)) {
+                let height = DEPTH - depth;
+                proof.bitmap[height / 8] |= 1 << (height % 8);
+                proof.siblings.push(*hash);
+            }
+        }
+        proof
+    }
+
+    /// The number of distinct keys inserted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, depth: usize, prefix: [u8; 32]) -> [u8; 32] {
+        self.nodes
+            .get(&(depth, prefix))
+            .copied()
+            .unwrap_or(self.empty[DEPTH - depth])
+    }
+}
+
+impl Default for SparseMerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Checks a [`SparseMerkleTree::proof`] that `key` holds `value` under `root`, or, when
+/// `value` is `None`, that `key` was never inserted.
+///
+/// Returns `false` rather than panicking for any malformed proof.
+pub fn verify_sparse_proof(
+    root: [u8; 32],
+    key: &[u8],
+    value: Option<&[u8]>,
+    proof: &SparseProof,
+) -> bool {
+    let included = proof.bitmap.iter().map(|byte| byte.count_ones() as usize);
+    if included.sum::<usize>() != proof.siblings.len() {
+        return false;
+    }
+
+    let path = Keccak256::digest(key);
+    let mut node = value.map_or([0u8; 32], Keccak256::digest);
+    let mut empty = [0u8; 32];
+    let mut siblings = proof.siblings.iter();
+    for height in 0..DEPTH {
+        let sibling = if proof.bitmap[height / 8] >> (height % 8) & 1 == 1 {
+            match siblings.next() {
+                Some(sibling) => *sibling,
+                None => return false,
+            }
+        } else {
+            empty
+        };
+        node = if bit(&path, DEPTH - 1 - height) {
+            hash_nodes(&sibling, &node)
+        } else {
+            hash_nodes(&node, &sibling)
+        };
+        empty = hash_nodes(&empty, &empty);
+    }
+    node == root
+}
+
+// Whether the path turns right at `depth`, the bit below the node at that depth
+fn bit(path: &[u8; 32], depth: usize) -> bool {
+    path[depth / 8] >> (7 - depth % 8) & 1 == 1
+}
+
+fn flip_bit(mut path: [u8; 32], depth: usize) -> [u8; 32] {
+    path[depth / 8] ^= 1 << (7 - depth % 8);
+    path
+}
+
+// The first `depth` bits of `path`, identifying the node at that depth
+fn prefix(path: &[u8; 32], depth: usize) -> [u8; 32] {
+    let mut prefix = [0u8; 32];
+    prefix[..depth / 8].copy_from_slice(&path[..depth / 8]);
+    if !depth.is_multiple_of(8) {
+        prefix[depth / 8] = path[depth / 8] & !(0xff >> (depth % 8));
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_tree() {
+        let tree = SparseMerkleTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), tree.empty[DEPTH]);
+
+        let proof = tree.proof(b"missing");
+        assert!(proof.siblings.is_empty());
+        assert!(verify_sparse_proof(tree.root(), b"missing", None, &proof));
+        assert!(!verify_sparse_proof(
+            tree.root(),
+            b"missing",
+            Some(b""),
+            &proof
+        ));
+    }
+
+    #[test]
+    fn membership_and_non_membership() {
+        let mut tree = SparseMerkleTree::new();
+        for i in 0..20u8 {
+            tree.insert(&[i], &[i, i]);
+        }
+        assert_eq!(tree.len(), 20);
+        let root = tree.root();
+
+        for i in 0..20u8 {
+            let proof = tree.proof(&[i]);
+            assert!(verify_sparse_proof(root, &[i], Some(&[i, i]), &proof));
+            assert!(!verify_sparse_proof(root, &[i], Some(&[i]), &proof));
+            assert!(!verify_sparse_proof(root, &[i], None, &proof));
+        }
+
+        let proof = tree.proof(b"absent");
+        assert!(verify_sparse_proof(root, b"absent", None, &proof));
+        assert!(!verify_sparse_proof(root, b"absent", Some(b""), &proof));
+        assert!(!verify_sparse_proof(root, &[0], None, &proof));
+    }
+
+    #[test]
+    fn rejects_tampered_proofs() {
+        let mut tree = SparseMerkleTree::new();
+        tree.insert(b"a", b"1");
+        tree.insert(b"b", b"2");
+        let proof = tree.proof(b"a");
+        assert!(verify_sparse_proof(tree.root(), b"a", Some(b"1"), &proof));
+
+        let mut tampered = proof.clone();
+        tampered.siblings[0][0] ^= 1;
+        assert!(!verify_sparse_proof(
+            tree.root(),
+            b"a",
+            Some(b"1"),
+            &tampered
+        ));
+
+        let mut extra_bit = proof.clone();
+        extra_bit.bitmap[0] ^= 1;
+        assert!(!verify_sparse_proof(
+            tree.root(),
+            b"a",
+            Some(b"1"),
+            &extra_bit
+        ));
+    }
+
+    #[test]
+    fn root_is_independent_of_insertion_order() {
+        let mut forward = SparseMerkleTree::new();
+        let mut backward = SparseMerkleTree::new();
+        for i in 0..10u8 {
+            forward.insert(&[i], b"old");
+            backward.insert(&[9 - i], b"new");
+        }
+        assert_ne!(forward.root(), backward.root());
+
+        for i in 0..10u8 {
+            forward.insert(&[i], b"new");
+        }
+        assert_eq!(forward.len(), 10);
+        assert_eq!(forward.root(), backward.root());
+    }
+}